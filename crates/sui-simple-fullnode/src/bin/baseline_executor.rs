@@ -1,14 +1,21 @@
 use clap::*;
 use std::collections::HashSet;
 use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::Instant;
 use sui_adapter::execution_engine;
 use sui_adapter::execution_mode;
 use sui_config::{Config, NodeConfig};
 use sui_core::authority::epoch_start_configuration::EpochStartConfiguration;
 use sui_core::transaction_input_checker::get_gas_status_no_epoch_store_experimental;
+use sui_types::effects::TransactionEffectsAPI;
+use sui_simple_fullnode::AnyReplayStore;
 use sui_simple_fullnode::MemoryBackedStore;
+use sui_simple_fullnode::ReplaySnapshot;
+use sui_simple_fullnode::ReplayStore;
+use sui_simple_fullnode::RocksDbBackedStore;
 use sui_simple_fullnode::SequenceWorkerState;
+use sui_simple_fullnode::WriteThroughCache;
 use sui_types::message_envelope::Message;
 use sui_types::messages::InputObjectKind;
 use sui_types::messages::InputObjects;
@@ -37,6 +44,76 @@ const GIT_REVISION: &str = {
 };
 const VERSION: &str = const_str::concat!(env!("CARGO_PKG_VERSION"), "-", GIT_REVISION);
 
+/// Handles one recomputed-vs-certified effects digest mismatch according to
+/// `--on-mismatch`: always logs, optionally computes and records a
+/// structured diff (`collect`), and aborts unless the operator asked to
+/// push through divergences.
+fn handle_effects_mismatch(
+    on_mismatch: sui_simple_fullnode::OnMismatch,
+    checkpoint_seq: u64,
+    tx_digest: sui_types::digests::TransactionDigest,
+    expected_effects_digest: sui_types::digests::TransactionEffectsDigest,
+    expected_effects: Option<sui_types::effects::TransactionEffects>,
+    actual_effects: &sui_types::effects::TransactionEffects,
+    mismatch_report: &mut sui_simple_fullnode::MismatchReport,
+    replay_prometheus_metrics: &sui_simple_fullnode::ReplayPrometheusMetrics,
+) {
+    replay_prometheus_metrics.mismatch_count.inc();
+    println!("Effects mismatch at checkpoint {checkpoint_seq}, tx {tx_digest}");
+    if matches!(on_mismatch, sui_simple_fullnode::OnMismatch::Collect) {
+        match &expected_effects {
+            Some(expected) => {
+                let diff = sui_simple_fullnode::diff_effects(
+                    checkpoint_seq,
+                    tx_digest,
+                    expected_effects_digest,
+                    expected,
+                    actual_effects,
+                );
+                mismatch_report.record(diff);
+            }
+            // The checkpoint-certified effects aren't available (e.g.
+            // pruned), so there's nothing to diff against. Still record the
+            // mismatch by digest so the final report's count isn't silently
+            // short of what actually happened.
+            None => mismatch_report.record_undiagnosed(sui_simple_fullnode::UndiagnosedMismatch {
+                checkpoint_seq,
+                tx_digest,
+                expected_effects_digest,
+                actual_effects_digest: actual_effects.digest(),
+            }),
+        }
+    }
+    if matches!(on_mismatch, sui_simple_fullnode::OnMismatch::Abort) {
+        panic!("Effects digest mismatch at checkpoint {checkpoint_seq}, tx {tx_digest}");
+    }
+}
+
+/// Captures the live object set and current epoch context into a
+/// [`ReplaySnapshot`], so replay can resume from `next_checkpoint_seq`
+/// instead of genesis after a restart.
+fn build_snapshot(
+    next_checkpoint_seq: u64,
+    memory_store: &AnyReplayStore,
+    epoch_store: &sui_core::authority::authority_per_epoch_store::AuthorityPerEpochStore,
+    epoch_start_config: &EpochStartConfiguration,
+) -> ReplaySnapshot {
+    ReplaySnapshot {
+        next_checkpoint_seq,
+        objects: memory_store.snapshot_objects(),
+        epoch: epoch_store.epoch(),
+        protocol_version: epoch_store.protocol_config().version.as_u64(),
+        epoch_start_configuration: epoch_start_config.clone(),
+    }
+}
+
+#[derive(Copy, Clone, ValueEnum)]
+#[clap(rename_all = "kebab-case")]
+enum StoreBackend {
+    Memory,
+    Rocksdb,
+}
+
 #[derive(Parser)]
 #[clap(rename_all = "kebab-case")]
 #[clap(name = env!("CARGO_BIN_NAME"))]
@@ -55,6 +132,85 @@ struct Args {
 
     #[clap(long, help = "Specify address to listen on")]
     listen_address: Option<Multiaddr>,
+
+    /// Which object store backend to replay against. `rocksdb` allows full
+    /// chain history replays that don't fit in memory.
+    #[clap(long, value_enum, default_value_t = StoreBackend::Memory)]
+    store_backend: StoreBackend,
+
+    /// Number of staged object writes the write-through cache holds before
+    /// flushing to the backing store. Only meaningful with `--store-backend
+    /// rocksdb`.
+    #[clap(long, default_value_t = 100_000)]
+    cache_size: usize,
+
+    /// How many checkpoints the sequencing task may fetch ahead of
+    /// execution. Higher values overlap more network latency with
+    /// execution at the cost of more in-flight checkpoint contents.
+    #[clap(long, default_value_t = 64)]
+    pipeline_depth: usize,
+
+    /// Run a checkpoint's transactions optimistically across N threads
+    /// (Block-STM style) instead of one at a time. 1 (the default) keeps
+    /// the original sequential execution.
+    #[clap(long, default_value_t = 1)]
+    parallel: usize,
+
+    /// Verify each checkpoint's aggregated validator signature against the
+    /// current epoch committee, and each epoch boundary's signed next
+    /// committee against the one replay derives, stopping on the first
+    /// mismatch. Turns the tool into a trust-minimized light verifier
+    /// rather than a store-trusting re-executor.
+    #[clap(long)]
+    verify_signatures: bool,
+
+    /// What to do when recomputed effects don't match a checkpoint's
+    /// certified effects: stop immediately, log and continue, or log,
+    /// continue, and dump a JSON report of every mismatch at the end.
+    /// "The end" is reached via `--stop-at-seq` or Ctrl-C; a `collect` run
+    /// with neither set never reaches the report write.
+    #[clap(long, value_enum, default_value_t = sui_simple_fullnode::OnMismatch::Abort)]
+    on_mismatch: sui_simple_fullnode::OnMismatch,
+
+    /// Where to write the mismatch report for `--on-mismatch collect`.
+    #[clap(long, default_value = "mismatches.json")]
+    mismatch_report_path: PathBuf,
+
+    /// How many checkpoints between automatic state snapshots. Also takes a
+    /// snapshot at every epoch boundary, regardless of this interval. Omit
+    /// to disable periodic snapshotting.
+    #[clap(long)]
+    snapshot_interval: Option<u64>,
+
+    /// Where automatic snapshots are written. A snapshot is named
+    /// `<prefix>.<checkpoint_seq>`; required if `--snapshot-interval` is set.
+    #[clap(long)]
+    snapshot_path_prefix: Option<PathBuf>,
+
+    /// Resume replay from a snapshot written by a previous run instead of
+    /// genesis: restores the live object set and epoch context, then
+    /// continues the pipeline from the snapshot's checkpoint sequence.
+    #[clap(long)]
+    resume_from: Option<PathBuf>,
+
+    /// How often, in seconds, to print a rolling throughput summary.
+    #[clap(long, default_value_t = 10)]
+    progress_interval_secs: u64,
+
+    /// Serve the replay's Prometheus metrics (checkpoints/s, tx/s, current
+    /// epoch, store size, mismatch count) on this address. Omit to disable.
+    #[clap(long)]
+    metrics_address: Option<std::net::SocketAddr>,
+
+    /// Stop after processing this checkpoint (inclusive), instead of
+    /// running forever. This binary has no watermark of its own
+    /// (`checkpoint_sync_supported()` is false, so there's no network sync
+    /// client to ask "are we caught up?"), so without this a bounded
+    /// historical replay never reaches the `--on-mismatch collect` report
+    /// or any other end-of-run step. Ctrl-C also stops the run, before the
+    /// next checkpoint starts, without a `--stop-at-seq` set.
+    #[clap(long)]
+    stop_at_seq: Option<u64>,
 }
 
 #[tokio::main]
@@ -62,160 +218,439 @@ async fn main() {
     let args = Args::parse();
     let config = NodeConfig::load(&args.config_path).unwrap();
     let genesis = config.genesis().expect("Could not load genesis");
-    let mut sw_state = SequenceWorkerState::new(&config).await;
+    let sw_state = Arc::new(SequenceWorkerState::new(&config).await);
 
     if let Some(watermark) = args.download {
+        if !sui_simple_fullnode::checkpoint_sync_supported() {
+            eprintln!(
+                "--download requires a checkpoint sync client, which this binary doesn't \
+                 implement (that lives in the full fullnode sync client); populate \
+                 `checkpoint_store` out-of-band instead, e.g. by pointing --config-path at a \
+                 node that shares its on-disk store."
+            );
+            std::process::exit(1);
+        }
         sw_state.handle_download(watermark, &config).await;
     }
 
     if args.execute {
-        let mut memory_store = MemoryBackedStore::new();
-        for obj in genesis.objects() {
-            memory_store
-                .objects
-                .insert(obj.id(), (obj.compute_object_reference(), obj.clone()));
-        }
-
-        let mut protocol_config = sw_state.epoch_store.protocol_config();
-        let mut move_vm = sw_state.epoch_store.move_vm();
-        let mut epoch_start_config = sw_state.epoch_store.epoch_start_config();
-        let mut reference_gas_price = sw_state.epoch_store.reference_gas_price();
-
-        let genesis_seq = genesis.checkpoint().into_summary_and_sequence().0;
-
-        let highest_synced_seq = match sw_state
-            .checkpoint_store
-            .get_highest_synced_checkpoint_seq_number()
-            .expect("error")
-        {
-            Some(highest) => highest,
-            None => 0,
+        let mut memory_store = match args.store_backend {
+            StoreBackend::Memory => {
+                AnyReplayStore::Memory(WriteThroughCache::new(MemoryBackedStore::new(), args.cache_size))
+            }
+            StoreBackend::Rocksdb => AnyReplayStore::RocksDb(WriteThroughCache::new(
+                RocksDbBackedStore::open(config.db_path().join("replay_objects")),
+                args.cache_size,
+            )),
         };
-        let highest_executed_seq = match sw_state
-            .checkpoint_store
-            .get_highest_executed_checkpoint_seq_number()
-            .expect("error")
-        {
-            Some(highest) => highest,
-            None => 0,
+        let (mut epoch_store, start_seq) = if let Some(resume_from) = &args.resume_from {
+            let snapshot = ReplaySnapshot::read_from_file(resume_from)
+                .expect("Could not read resume snapshot");
+            let epoch_store = sw_state.epoch_store_for_resume(&config, &snapshot);
+            for (id, value) in snapshot.objects {
+                memory_store.insert_object(id, value);
+            }
+            println!(
+                "Resumed from {} at checkpoint {}, epoch {}",
+                resume_from.display(),
+                snapshot.next_checkpoint_seq,
+                epoch_store.epoch()
+            );
+            (epoch_store, snapshot.next_checkpoint_seq)
+        } else {
+            for obj in genesis.objects() {
+                memory_store.insert_object(obj.id(), (obj.compute_object_reference(), obj.clone()));
+            }
+            let genesis_seq = genesis.checkpoint().into_summary_and_sequence().0;
+            (sw_state.epoch_store.clone(), genesis_seq)
         };
-        println!("Highest synced {}", highest_synced_seq);
-        println!("Highest executed {}", highest_executed_seq);
+        let mut protocol_config = epoch_store.protocol_config();
+        let mut move_vm = epoch_store.move_vm();
+        let mut epoch_start_config = epoch_store.epoch_start_config();
+        let mut reference_gas_price = epoch_store.reference_gas_price();
+
+        if let Some(interval) = args.snapshot_interval {
+            assert!(
+                args.snapshot_path_prefix.is_some(),
+                "--snapshot-interval requires --snapshot-path-prefix"
+            );
+            assert!(interval > 0, "--snapshot-interval must be greater than 0");
+        }
 
-        let now = Instant::now();
-        let mut num_tx: usize = 0;
-        for checkpoint_seq in genesis_seq..highest_synced_seq {
-            let checkpoint_summary = sw_state
+        if !sui_simple_fullnode::checkpoint_sync_supported()
+            && sw_state
                 .checkpoint_store
-                .get_checkpoint_by_sequence_number(checkpoint_seq)
+                .get_checkpoint_by_sequence_number(start_seq)
                 .expect("Cannot get checkpoint")
-                .expect("Checkpoint is None");
+                .is_none()
+        {
+            eprintln!(
+                "Checkpoint {start_seq} isn't in the local checkpoint store, and this binary \
+                 has no checkpoint sync client built in (that lives in the full fullnode sync \
+                 client). Populate `checkpoint_store` out-of-band before using --execute, e.g. \
+                 by pointing --config-path at a node that shares its on-disk store."
+            );
+            std::process::exit(1);
+        }
 
-            if checkpoint_seq % 1000 == 0 {
-                println!("{}", checkpoint_seq);
+        let (mut checkpoint_rx, fetch_tx, _sequencing_task) = Arc::clone(&sw_state).spawn_sequencing_task(
+            config.clone(),
+            start_seq,
+            args.pipeline_depth,
+        );
+
+        let prometheus_registry = prometheus::Registry::new();
+        let replay_prometheus_metrics = sui_simple_fullnode::ReplayPrometheusMetrics::new(&prometheus_registry);
+        if let Some(address) = args.metrics_address {
+            sui_simple_fullnode::start_metrics_server(address, &prometheus_registry);
+        }
+
+        let mut progress = sui_simple_fullnode::ProgressReporter::new(Instant::now());
+        let progress_interval = std::time::Duration::from_secs(args.progress_interval_secs);
+        let mut mismatch_report = sui_simple_fullnode::MismatchReport::default();
+        loop {
+            let sequenced = tokio::select! {
+                sequenced = checkpoint_rx.recv() => match sequenced {
+                    Some(sequenced) => sequenced,
+                    None => break,
+                },
+                _ = tokio::signal::ctrl_c() => {
+                    println!("Received Ctrl-C, stopping before the next checkpoint");
+                    break;
+                }
+            };
+            let checkpoint_seq = sequenced.sequence_number;
+
+            if args.verify_signatures {
+                if let Err(failure) =
+                    sui_simple_fullnode::verify_checkpoint_summary(&sequenced.summary, epoch_store.committee())
+                {
+                    eprintln!(
+                        "Checkpoint {} failed signature verification: {}",
+                        failure.checkpoint_seq, failure.reason
+                    );
+                    std::process::exit(1);
+                }
             }
 
-            let (_seq, summary) = checkpoint_summary.into_summary_and_sequence();
-            let contents = sw_state
-                .checkpoint_store
-                .get_checkpoint_contents(&summary.content_digest)
-                .expect("Contents must exist")
-                .expect("Contents must exist");
-            num_tx += contents.size();
-            for tx_digest in contents.iter() {
-                let tx = sw_state
-                    .store
-                    .get_transaction_block(&tx_digest.transaction)
-                    .expect("Transaction exists")
-                    .expect("Transaction exists");
-                let tx_data = tx.data().transaction_data();
-                let input_object_kinds = tx_data
-                    .input_objects()
-                    .expect("Cannot get input object kinds");
-                // println!("Digest: {:?}", tx_digest);
-
-                let mut input_object_data = Vec::new();
-                for kind in &input_object_kinds {
-                    let obj = match kind {
-                        InputObjectKind::MovePackage(id)
-                        | InputObjectKind::SharedMoveObject { id, .. }
-                        | InputObjectKind::ImmOrOwnedMoveObject((id, _, _)) => {
-                            memory_store.objects.get(&id).expect("Object missing?")
-                        }
-                    };
-                    input_object_data.push(obj.1.clone());
+            let summary = sequenced.summary.into_summary_and_sequence().1;
+            let contents = sequenced.contents;
+
+            // Accumulated below as transactions are fetched and their
+            // writes committed: `contents` itself is just digest pairs, not
+            // the actual transaction/object payloads replay fetches and
+            // processes, so it isn't a meaningful stand-in for bytes
+            // processed.
+            let mut checkpoint_bytes = 0u64;
+            if args.parallel > 1 {
+                // Prepare every transaction sequentially first: this only
+                // loads the transaction itself and its declared input kinds
+                // (fixed, checkpoint-independent metadata), never an object's
+                // value. Anything that depends on object contents — gas
+                // status included, since the gas payment object can be
+                // mutated by an earlier transaction in the same checkpoint —
+                // is resolved per-`txn_idx` through `SpeculativeStore` inside
+                // the speculative execution closure below, so it's validated
+                // through `MVMemory` like every other read.
+                struct PreparedTxn {
+                    tx: sui_types::messages::VerifiedTransaction,
+                    input_object_kinds: Vec<InputObjectKind>,
+                    expected_effects: sui_types::digests::TransactionEffectsDigest,
                 }
 
-                let gas_status = get_gas_status_no_epoch_store_experimental(
-                    &input_object_data,
-                    tx_data.gas(),
-                    protocol_config,
-                    reference_gas_price,
-                    &tx_data,
-                )
-                .await
-                .expect("Could not get gas");
-
-                let input_objects = InputObjects::new(
-                    input_object_kinds
-                        .into_iter()
-                        .zip(input_object_data.into_iter())
-                        .collect(),
-                );
-                let shared_object_refs = input_objects.filter_shared_objects();
-                let transaction_dependencies = input_objects.transaction_dependencies();
-
-                let temporary_store = TemporaryStore::new(
-                    &memory_store,
-                    input_objects,
-                    *tx.digest(),
-                    protocol_config,
-                );
+                let mut prepared = Vec::new();
+                for tx_digest in contents.iter() {
+                    let tx = sw_state
+                        .store
+                        .get_transaction_block(&tx_digest.transaction)
+                        .expect("Transaction exists")
+                        .expect("Transaction exists");
+                    let input_object_kinds = tx
+                        .data()
+                        .transaction_data()
+                        .input_objects()
+                        .expect("Cannot get input object kinds");
+                    checkpoint_bytes += bcs::serialized_size(&tx).expect("transaction should always serialize") as u64;
+                    prepared.push(PreparedTxn {
+                        tx,
+                        input_object_kinds,
+                        expected_effects: tx_digest.effects,
+                    });
+                }
+
+                // `get_gas_status_no_epoch_store_experimental` is async, but
+                // the speculative execution closure below runs on the
+                // `std::thread::scope` worker threads, not as a tokio task;
+                // drive it to completion synchronously on whichever worker
+                // thread calls it.
+                let runtime_handle = tokio::runtime::Handle::current();
 
-                let (kind, signer, gas) = tx_data.execution_parts();
+                let declared_inputs: Vec<_> = prepared
+                    .iter()
+                    .map(|p| {
+                        p.input_object_kinds
+                            .iter()
+                            .map(|kind| match kind {
+                                InputObjectKind::MovePackage(id)
+                                | InputObjectKind::SharedMoveObject { id, .. }
+                                | InputObjectKind::ImmOrOwnedMoveObject((id, _, _)) => *id,
+                            })
+                            .collect()
+                    })
+                    .collect();
 
-                if let TransactionKind::ChangeEpoch(_) = kind {
-                    println!("Change epoch at checkpoint {}", checkpoint_seq)
-                    // check if this is the last transaction of the epoch
+                let mv_memory = sui_simple_fullnode::block_stm::MVMemory::new();
+                let results = sui_simple_fullnode::execute_checkpoint_parallel(
+                    args.parallel,
+                    declared_inputs,
+                    &mv_memory,
+                    |txn_idx, _incarnation| {
+                        let prepared_txn = &prepared[txn_idx];
+                        let speculative_store = sui_simple_fullnode::SpeculativeStore::new(
+                            &memory_store,
+                            &mv_memory,
+                            txn_idx,
+                        );
+                        let tx_data = prepared_txn.tx.data().transaction_data();
+                        let input_object_data: Vec<_> = prepared_txn
+                            .input_object_kinds
+                            .iter()
+                            .map(|kind| {
+                                let id = match kind {
+                                    InputObjectKind::MovePackage(id)
+                                    | InputObjectKind::SharedMoveObject { id, .. }
+                                    | InputObjectKind::ImmOrOwnedMoveObject((id, _, _)) => id,
+                                };
+                                speculative_store
+                                    .get_object(id)
+                                    .expect("speculative read should not fail")
+                                    .expect("Object missing?")
+                            })
+                            .collect();
+                        let gas_status = runtime_handle
+                            .block_on(get_gas_status_no_epoch_store_experimental(
+                                &input_object_data,
+                                tx_data.gas(),
+                                protocol_config,
+                                reference_gas_price,
+                                &tx_data,
+                            ))
+                            .expect("Could not get gas");
+                        let input_objects = InputObjects::new(
+                            prepared_txn
+                                .input_object_kinds
+                                .clone()
+                                .into_iter()
+                                .zip(input_object_data.into_iter())
+                                .collect(),
+                        );
+                        let shared_object_refs = input_objects.filter_shared_objects();
+                        let transaction_dependencies = input_objects.transaction_dependencies();
+
+                        let temporary_store = TemporaryStore::new(
+                            &speculative_store,
+                            input_objects,
+                            *prepared_txn.tx.digest(),
+                            protocol_config,
+                        );
+                        let (kind, signer, gas) = tx_data.execution_parts();
+
+                        if let TransactionKind::ChangeEpoch(_) = kind {
+                            println!("Change epoch at checkpoint {}", checkpoint_seq)
+                        }
+
+                        let (inner_temp_store, effects, _execution_error) =
+                            execution_engine::execute_transaction_to_effects::<execution_mode::Normal, _>(
+                                shared_object_refs,
+                                temporary_store,
+                                kind,
+                                signer,
+                                &gas,
+                                *prepared_txn.tx.digest(),
+                                transaction_dependencies,
+                                move_vm,
+                                gas_status,
+                                &epoch_start_config.epoch_data(),
+                                protocol_config,
+                                sw_state.metrics.clone(),
+                                false,
+                                &HashSet::new(),
+                            );
+
+                        let saw_estimate = speculative_store.saw_estimate();
+                        let mut writes: Vec<_> = inner_temp_store
+                            .deleted
+                            .iter()
+                            .map(|(id, _)| (*id, None))
+                            .collect();
+                        writes.extend(
+                            inner_temp_store
+                                .written
+                                .into_iter()
+                                .map(|(id, (oref, obj, _))| (id, Some((oref, obj)))),
+                        );
+
+                        sui_simple_fullnode::SpeculativeResult {
+                            effects,
+                            read_set: speculative_store.into_read_set(),
+                            saw_estimate,
+                            writes,
+                        }
+                    },
+                );
+
+                // Commit in checkpoint order: this is what makes the
+                // parallel path observably equivalent to the sequential one.
+                for (prepared_txn, result) in prepared.iter().zip(results.into_iter()) {
+                    if result.effects.digest() != prepared_txn.expected_effects {
+                        let expected_effects = sw_state
+                            .store
+                            .get_transaction_effects(&prepared_txn.expected_effects)
+                            .ok()
+                            .flatten();
+                        handle_effects_mismatch(
+                            args.on_mismatch,
+                            checkpoint_seq,
+                            *prepared_txn.tx.digest(),
+                            prepared_txn.expected_effects,
+                            expected_effects,
+                            &result.effects,
+                            &mut mismatch_report,
+                            &replay_prometheus_metrics,
+                        );
+                    }
+                    for (id, value) in result.writes {
+                        match value {
+                            Some(obj) => {
+                                checkpoint_bytes +=
+                                    bcs::serialized_size(&obj.1).expect("written object should always serialize")
+                                        as u64;
+                                memory_store.insert_object(id, obj);
+                            }
+                            None => memory_store.delete_object(&id),
+                        }
+                    }
                 }
+            } else {
+                for tx_digest in contents.iter() {
+                    let tx = sw_state
+                        .store
+                        .get_transaction_block(&tx_digest.transaction)
+                        .expect("Transaction exists")
+                        .expect("Transaction exists");
+                    let tx_data = tx.data().transaction_data();
+                    let input_object_kinds = tx_data
+                        .input_objects()
+                        .expect("Cannot get input object kinds");
+                    checkpoint_bytes += bcs::serialized_size(&tx).expect("transaction should always serialize") as u64;
+                    // println!("Digest: {:?}", tx_digest);
+
+                    let mut input_object_data = Vec::new();
+                    for kind in &input_object_kinds {
+                        let obj = match kind {
+                            InputObjectKind::MovePackage(id)
+                            | InputObjectKind::SharedMoveObject { id, .. }
+                            | InputObjectKind::ImmOrOwnedMoveObject((id, _, _)) => {
+                                memory_store.get_object(&id).expect("Object missing?")
+                            }
+                        };
+                        input_object_data.push(obj.1);
+                    }
+
+                    let gas_status = get_gas_status_no_epoch_store_experimental(
+                        &input_object_data,
+                        tx_data.gas(),
+                        protocol_config,
+                        reference_gas_price,
+                        &tx_data,
+                    )
+                    .await
+                    .expect("Could not get gas");
+
+                    let input_objects = InputObjects::new(
+                        input_object_kinds
+                            .into_iter()
+                            .zip(input_object_data.into_iter())
+                            .collect(),
+                    );
+                    let shared_object_refs = input_objects.filter_shared_objects();
+                    let transaction_dependencies = input_objects.transaction_dependencies();
 
-                let (inner_temp_store, effects, _execution_error) =
-                    execution_engine::execute_transaction_to_effects::<execution_mode::Normal, _>(
-                        shared_object_refs,
-                        temporary_store,
-                        kind,
-                        signer,
-                        &gas,
+                    let temporary_store = TemporaryStore::new(
+                        &memory_store,
+                        input_objects,
                         *tx.digest(),
-                        transaction_dependencies,
-                        move_vm,
-                        gas_status,
-                        &epoch_start_config.epoch_data(),
                         protocol_config,
-                        sw_state.metrics.clone(),
-                        false,
-                        &HashSet::new(),
                     );
 
-                // Critical check: are the effects the same?
-                if effects.digest() != tx_digest.effects {
-                    println!("Effects mismatch at checkpoint {}", checkpoint_seq);
-                    let old_effects = tx_digest.effects;
-                    println!("Past effects: {:?}", old_effects);
-                    println!("New effects: {:?}", effects);
-                }
-                assert!(
-                    effects.digest() == tx_digest.effects,
-                    "Effects digest mismatch"
-                );
+                    let (kind, signer, gas) = tx_data.execution_parts();
+
+                    if let TransactionKind::ChangeEpoch(_) = kind {
+                        println!("Change epoch at checkpoint {}", checkpoint_seq)
+                        // check if this is the last transaction of the epoch
+                    }
 
-                // And now we mutate the store.
-                // First delete:
-                for obj_del in &inner_temp_store.deleted {
-                    memory_store.objects.remove(obj_del.0);
+                    let (inner_temp_store, effects, _execution_error) =
+                        execution_engine::execute_transaction_to_effects::<execution_mode::Normal, _>(
+                            shared_object_refs,
+                            temporary_store,
+                            kind,
+                            signer,
+                            &gas,
+                            *tx.digest(),
+                            transaction_dependencies,
+                            move_vm,
+                            gas_status,
+                            &epoch_start_config.epoch_data(),
+                            protocol_config,
+                            sw_state.metrics.clone(),
+                            false,
+                            &HashSet::new(),
+                        );
+
+                    // Critical check: are the effects the same?
+                    if effects.digest() != tx_digest.effects {
+                        let expected_effects = sw_state.store.get_transaction_effects(&tx_digest.effects).ok().flatten();
+                        handle_effects_mismatch(
+                            args.on_mismatch,
+                            checkpoint_seq,
+                            tx_digest.transaction,
+                            tx_digest.effects,
+                            expected_effects,
+                            &effects,
+                            &mut mismatch_report,
+                            &replay_prometheus_metrics,
+                        );
+                    }
+
+                    // And now we mutate the store.
+                    // First delete:
+                    for obj_del in &inner_temp_store.deleted {
+                        memory_store.delete_object(obj_del.0);
+                    }
+                    for (obj_add_id, (oref, obj, _)) in inner_temp_store.written {
+                        checkpoint_bytes +=
+                            bcs::serialized_size(&obj).expect("written object should always serialize") as u64;
+                        memory_store.insert_object(obj_add_id, (oref, obj));
+                    }
                 }
-                for (obj_add_id, (oref, obj, _)) in inner_temp_store.written {
-                    memory_store.objects.insert(obj_add_id, (oref, obj));
+            }
+
+            progress.record_checkpoint(contents.size(), checkpoint_bytes);
+
+            // Checkpoint boundary: flush any staged writes so a disk-backed
+            // backend doesn't accumulate unbounded cache growth across checkpoints.
+            memory_store.flush();
+
+            if let (Some(interval), Some(prefix)) =
+                (args.snapshot_interval, &args.snapshot_path_prefix)
+            {
+                if checkpoint_seq % interval == 0 {
+                    let snapshot =
+                        build_snapshot(checkpoint_seq + 1, &memory_store, &epoch_store, &epoch_start_config);
+                    snapshot
+                        .write_to_file(&prefix.with_extension(checkpoint_seq.to_string()))
+                        .expect("Could not write snapshot");
                 }
             }
 
@@ -226,38 +661,106 @@ async fn main() {
                 let new_epoch_start_state = latest_state.into_epoch_start_state();
                 let next_epoch_committee = new_epoch_start_state.get_sui_committee();
                 let next_epoch = next_epoch_committee.epoch();
-                let last_checkpoint = sw_state
+                let mut last_checkpoint = sw_state
                     .checkpoint_store
-                    .get_epoch_last_checkpoint(sw_state.epoch_store.epoch())
-                    .expect("Error loading last checkpoint for current epoch")
-                    .expect("Could not load last checkpoint for current epoch");
+                    .get_epoch_last_checkpoint(epoch_store.epoch())
+                    .expect("Error loading last checkpoint for current epoch");
+                if last_checkpoint.is_none() {
+                    // The sequencer hasn't fetched this checkpoint yet;
+                    // request it on demand and wait for the ack, so we only
+                    // re-read `checkpoint_store` once the fetch has landed
+                    // instead of racing the sequencing task for it.
+                    let (ack_tx, ack_rx) = tokio::sync::oneshot::channel();
+                    if fetch_tx.send((checkpoint_seq, ack_tx)).await.is_ok() {
+                        let _ = ack_rx.await;
+                    }
+                    last_checkpoint = sw_state
+                        .checkpoint_store
+                        .get_epoch_last_checkpoint(epoch_store.epoch())
+                        .expect("Error loading last checkpoint for current epoch");
+                }
+                let Some(last_checkpoint) = last_checkpoint else {
+                    eprintln!(
+                        "Epoch {}'s boundary checkpoint isn't in the local checkpoint store, even \
+                         after requesting it on demand (this binary has no checkpoint sync client \
+                         built in; populate `checkpoint_store` out-of-band, e.g. by pointing \
+                         --config-path at a node that shares its on-disk store, and confirm it \
+                         hasn't been pruned past this checkpoint).",
+                        epoch_store.epoch()
+                    );
+                    std::process::exit(1);
+                };
                 println!(
                     "Last checkpoint sequence number: {}",
                     last_checkpoint.sequence_number(),
                 );
+                if args.verify_signatures {
+                    if let Err(failure) =
+                        sui_simple_fullnode::verify_committee_transition(&summary, &next_epoch_committee)
+                    {
+                        eprintln!(
+                            "Checkpoint {} failed committee transition verification: {}",
+                            failure.checkpoint_seq, failure.reason
+                        );
+                        std::process::exit(1);
+                    }
+                }
+
                 let epoch_start_configuration =
                     EpochStartConfiguration::new(new_epoch_start_state, *last_checkpoint.digest());
-                assert_eq!(sw_state.epoch_store.epoch() + 1, next_epoch);
-                sw_state.epoch_store = sw_state.epoch_store.new_at_next_epoch(
+                assert_eq!(epoch_store.epoch() + 1, next_epoch);
+                epoch_store = epoch_store.new_at_next_epoch(
                     config.protocol_public_key(),
                     next_epoch_committee,
                     epoch_start_configuration,
                     sw_state.store.clone(),
                     &config.expensive_safety_check_config,
                 );
-                println!("New epoch store has epoch {}", sw_state.epoch_store.epoch());
-                protocol_config = sw_state.epoch_store.protocol_config();
-                move_vm = sw_state.epoch_store.move_vm();
-                epoch_start_config = sw_state.epoch_store.epoch_start_config();
-                reference_gas_price = sw_state.epoch_store.reference_gas_price();
+                println!("New epoch store has epoch {}", epoch_store.epoch());
+                protocol_config = epoch_store.protocol_config();
+                move_vm = epoch_store.move_vm();
+                epoch_start_config = epoch_store.epoch_start_config();
+                reference_gas_price = epoch_store.reference_gas_price();
+
+                if let (Some(_), Some(prefix)) = (args.snapshot_interval, &args.snapshot_path_prefix) {
+                    let snapshot =
+                        build_snapshot(checkpoint_seq + 1, &memory_store, &epoch_store, &epoch_start_config);
+                    snapshot
+                        .write_to_file(&prefix.with_extension(format!("{checkpoint_seq}-epoch-boundary")))
+                        .expect("Could not write epoch-boundary snapshot");
+                }
             }
-        } // for loop over checkpoints
 
-        // print TPS
-        let elapsed = now.elapsed();
-        println!(
-            "TPS: {}",
-            1000.0 * num_tx as f64 / elapsed.as_millis() as f64
-        );
+            if progress.due(progress_interval, Instant::now()) {
+                let store_object_count = memory_store.object_count();
+                let (tx_per_sec, checkpoints_per_sec) =
+                    progress.report(checkpoint_seq, store_object_count, Instant::now());
+                replay_prometheus_metrics.tx_per_sec.set(tx_per_sec);
+                replay_prometheus_metrics.checkpoints_per_sec.set(checkpoints_per_sec);
+                replay_prometheus_metrics.current_epoch.set(epoch_store.epoch() as i64);
+                replay_prometheus_metrics
+                    .store_object_count
+                    .set(store_object_count as i64);
+            }
+
+            if args.stop_at_seq.is_some_and(|stop_at_seq| checkpoint_seq >= stop_at_seq) {
+                println!("Reached --stop-at-seq {}, stopping", checkpoint_seq);
+                break;
+            }
+        } // pipeline loop over checkpoints
+
+        // Reached via --stop-at-seq or Ctrl-C breaking the loop above; an
+        // unbounded run with neither set never gets here.
+        if matches!(args.on_mismatch, sui_simple_fullnode::OnMismatch::Collect) {
+            mismatch_report
+                .write_to_file(&args.mismatch_report_path)
+                .expect("Could not write mismatch report");
+            println!(
+                "Wrote {} mismatch(es) ({} undiagnosed) to {}",
+                mismatch_report.total_mismatches(),
+                mismatch_report.undiagnosed.len(),
+                args.mismatch_report_path.display()
+            );
+        }
     } // if args.execute
 }
\ No newline at end of file
@@ -0,0 +1,278 @@
+//! A small Block-STM style optimistic scheduler for replaying the
+//! transactions of a single checkpoint across a thread pool while
+//! guaranteeing the same result as running them sequentially in checkpoint
+//! order.
+//!
+//! Transactions execute speculatively against a [`MVMemory`] keyed by
+//! `(ObjectID, txn_idx, incarnation)`. A transaction's read-set records which
+//! version of each object it actually observed; validation re-checks that
+//! read-set against the current multi-version state, and a mismatch causes
+//! the transaction (and anything marked dirty by it) to abort and re-run
+//! with an incremented incarnation. Writes are committed to the base store
+//! strictly in `txn_idx` order once every transaction has validated clean.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use sui_types::base_types::ObjectID;
+
+/// A write recorded by transaction `txn_idx`, incarnation `incarnation`.
+/// `Estimate` stands in for an aborted incarnation's write: it still marks
+/// the slot occupied so a higher-index reader that depends on it is forced to
+/// wait/retry rather than silently falling through to an older version.
+enum MVEntry<V> {
+    Write { incarnation: usize, value: Option<V> },
+    Estimate,
+}
+
+/// Multi-version store keyed by object id. Each object maps to the set of
+/// transaction indices that have written it so far, sorted so a reader can
+/// binary-search for the highest index below its own.
+pub struct MVMemory<V> {
+    versions: Mutex<HashMap<ObjectID, std::collections::BTreeMap<usize, MVEntry<V>>>>,
+}
+
+/// What a read of `id` by `reader_idx` resolved to.
+pub enum ReadResult<V> {
+    /// Resolved to transaction `txn_idx`'s incarnation `incarnation` write
+    /// (or deletion, if `value` is `None`).
+    Version {
+        txn_idx: usize,
+        incarnation: usize,
+        value: Option<V>,
+    },
+    /// Resolved to the committed base store (no prior transaction in this
+    /// checkpoint wrote `id`).
+    Base,
+    /// The nearest lower-index write is from an aborted incarnation that
+    /// hasn't re-executed yet; the reader must block or retry rather than
+    /// proceed with a value that may change.
+    Estimate { blocking_txn_idx: usize },
+}
+
+impl<V: Clone> MVMemory<V> {
+    pub fn new() -> Self {
+        Self {
+            versions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn read(&self, id: &ObjectID, reader_idx: usize) -> ReadResult<V> {
+        let versions = self.versions.lock().unwrap();
+        let Some(writes) = versions.get(id) else {
+            return ReadResult::Base;
+        };
+        match writes.range(..reader_idx).next_back() {
+            None => ReadResult::Base,
+            Some((&txn_idx, MVEntry::Estimate)) => ReadResult::Estimate {
+                blocking_txn_idx: txn_idx,
+            },
+            Some((&txn_idx, MVEntry::Write { incarnation, value })) => ReadResult::Version {
+                txn_idx,
+                incarnation: *incarnation,
+                value: value.clone(),
+            },
+        }
+    }
+
+    pub fn record_write(&self, id: ObjectID, txn_idx: usize, incarnation: usize, value: Option<V>) {
+        self.versions
+            .lock()
+            .unwrap()
+            .entry(id)
+            .or_default()
+            .insert(txn_idx, MVEntry::Write { incarnation, value });
+    }
+
+    /// Marks every object `txn_idx` previously wrote as an `Estimate`,
+    /// forcing any transaction that already read through one of those
+    /// writes to be revalidated.
+    pub fn mark_estimate(&self, txn_idx: usize, written: &HashSet<ObjectID>) {
+        let mut versions = self.versions.lock().unwrap();
+        for id in written {
+            if let Some(writes) = versions.get_mut(id) {
+                writes.insert(txn_idx, MVEntry::Estimate);
+            }
+        }
+    }
+}
+
+/// One transaction's recorded read-set: for each object read, the
+/// `(txn_idx, incarnation)` version it resolved to, or `None` for a read
+/// that resolved to the base store.
+pub type ReadSet = Vec<(ObjectID, Option<(usize, usize)>)>;
+
+/// Dispatches execution tasks over `num_txns` transactions via a shared
+/// atomic cursor, in the style of Block-STM's collaborative scheduling: any
+/// idle worker thread can pick up the next transaction to execute. Validation
+/// is not dispatched as a separate task; the worker that executed a
+/// transaction validates its read-set right afterwards (see
+/// [`crate::parallel::execute_checkpoint_parallel`]).
+pub struct Scheduler {
+    num_txns: usize,
+    next_to_try: AtomicUsize,
+    incarnations: Vec<AtomicUsize>,
+    /// Each transaction's declared `input_objects()` (owned + shared).
+    /// Exposed via [`Scheduler::declared_inputs`] for callers that need it
+    /// (e.g. building a read-set); `next_task` does not consult it, since
+    /// any dispatch-time conflict hint would have to be re-offered once the
+    /// blocking lower index commits, which a plain monotonic cursor can't
+    /// do — dispatch unconditionally and let validation prove a read was
+    /// stale instead.
+    declared_inputs: Vec<HashSet<ObjectID>>,
+}
+
+pub enum Task {
+    Execute { txn_idx: usize, incarnation: usize },
+    /// The cursor has run past every transaction. This does not necessarily
+    /// mean every transaction has committed: one may still be aborted and
+    /// rewind the cursor, so callers should keep polling until their own
+    /// count of committed transactions reaches the total.
+    Done,
+}
+
+impl Scheduler {
+    pub fn new(declared_inputs: Vec<HashSet<ObjectID>>) -> Self {
+        let num_txns = declared_inputs.len();
+        Self {
+            num_txns,
+            next_to_try: AtomicUsize::new(0),
+            incarnations: (0..num_txns).map(|_| AtomicUsize::new(0)).collect(),
+            declared_inputs,
+        }
+    }
+
+    pub fn next_task(&self) -> Task {
+        let txn_idx = self.next_to_try.fetch_add(1, Ordering::SeqCst);
+        if txn_idx >= self.num_txns {
+            return Task::Done;
+        }
+        let incarnation = self.incarnations[txn_idx].load(Ordering::SeqCst);
+        Task::Execute { txn_idx, incarnation }
+    }
+
+    /// Re-queues `txn_idx` with an incremented incarnation after a
+    /// validation failure, and returns that new incarnation. Marking the
+    /// aborted run's writes as estimates is left to the caller.
+    pub fn abort_and_requeue(&self, txn_idx: usize) -> usize {
+        let new_incarnation = self.incarnations[txn_idx].fetch_add(1, Ordering::SeqCst) + 1;
+        // Rewind the cursor so this transaction is retried rather than
+        // skipped; coarser than a real Block-STM dependency graph, but
+        // sufficient here since checkpoints are small.
+        self.next_to_try.fetch_min(txn_idx, Ordering::SeqCst);
+        new_incarnation
+    }
+
+    pub fn declared_inputs(&self, txn_idx: usize) -> &HashSet<ObjectID> {
+        &self.declared_inputs[txn_idx]
+    }
+
+    pub fn num_txns(&self) -> usize {
+        self.num_txns
+    }
+
+    pub fn incarnation(&self, txn_idx: usize) -> usize {
+        self.incarnations[txn_idx].load(Ordering::SeqCst)
+    }
+}
+
+/// Checks whether `read_set` (recorded during a speculative execution of
+/// `txn_idx`) is still valid against the current multi-version state. A
+/// mismatch means some lower-index transaction produced a new write that
+/// this execution didn't see, so its result can no longer be trusted.
+pub fn validate_read_set<V: Clone>(mv_memory: &MVMemory<V>, txn_idx: usize, read_set: &ReadSet) -> bool {
+    for (id, expected) in read_set {
+        let observed = match mv_memory.read(id, txn_idx) {
+            ReadResult::Base => None,
+            ReadResult::Version {
+                txn_idx, incarnation, ..
+            } => Some((txn_idx, incarnation)),
+            ReadResult::Estimate { .. } => return false,
+        };
+        if observed != *expected {
+            return false;
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_resolves_to_base_with_no_prior_writes() {
+        let mv_memory: MVMemory<u32> = MVMemory::new();
+        assert!(matches!(mv_memory.read(&ObjectID::random(), 5), ReadResult::Base));
+    }
+
+    #[test]
+    fn read_resolves_to_highest_lower_index_write() {
+        let mv_memory: MVMemory<u32> = MVMemory::new();
+        let id = ObjectID::random();
+        mv_memory.record_write(id, 1, 0, Some(10));
+        mv_memory.record_write(id, 3, 0, Some(30));
+
+        match mv_memory.read(&id, 5) {
+            ReadResult::Version { txn_idx, value, .. } => {
+                assert_eq!(txn_idx, 3);
+                assert_eq!(value, Some(30));
+            }
+            _ => panic!("expected a version read"),
+        }
+
+        // A reader at index 1 only sees writes below index 1, so neither
+        // write is visible and it falls through to the base store.
+        assert!(matches!(mv_memory.read(&id, 1), ReadResult::Base));
+    }
+
+    #[test]
+    fn mark_estimate_blocks_dependent_reads() {
+        let mv_memory: MVMemory<u32> = MVMemory::new();
+        let id = ObjectID::random();
+        mv_memory.record_write(id, 2, 0, Some(10));
+        mv_memory.mark_estimate(2, &HashSet::from([id]));
+
+        match mv_memory.read(&id, 5) {
+            ReadResult::Estimate { blocking_txn_idx } => assert_eq!(blocking_txn_idx, 2),
+            _ => panic!("expected an estimate read"),
+        }
+    }
+
+    #[test]
+    fn validate_read_set_detects_a_superseding_write() {
+        let mv_memory: MVMemory<u32> = MVMemory::new();
+        let id = ObjectID::random();
+        mv_memory.record_write(id, 3, 0, Some(30));
+
+        let read_set: ReadSet = vec![(id, Some((3, 0)))];
+        assert!(validate_read_set(&mv_memory, 5, &read_set));
+
+        // A new, lower-index write lands after the read-set was recorded:
+        // the previously-observed version is now stale.
+        mv_memory.record_write(id, 4, 0, Some(40));
+        assert!(!validate_read_set(&mv_memory, 5, &read_set));
+    }
+
+    #[test]
+    fn abort_and_requeue_bumps_incarnation_and_rewinds_the_cursor() {
+        let scheduler = Scheduler::new(vec![HashSet::new(), HashSet::new(), HashSet::new()]);
+        assert!(matches!(scheduler.next_task(), Task::Execute { txn_idx: 0, .. }));
+        assert!(matches!(scheduler.next_task(), Task::Execute { txn_idx: 1, .. }));
+
+        let new_incarnation = scheduler.abort_and_requeue(0);
+        assert_eq!(new_incarnation, 1);
+        assert_eq!(scheduler.incarnation(0), 1);
+
+        // The cursor rewound to the aborted index, so it's dispatched again
+        // before index 2, with the bumped incarnation.
+        assert!(matches!(
+            scheduler.next_task(),
+            Task::Execute {
+                txn_idx: 0,
+                incarnation: 1
+            }
+        ));
+    }
+}
@@ -0,0 +1,160 @@
+//! Structured diagnostics for the one invariant the whole tool exists to
+//! check: that recomputed `TransactionEffects` match what the checkpoint
+//! certified. Instead of printing both effects and panicking, this computes
+//! a per-object diff and lets the operator decide whether to stop, skip, or
+//! keep a running report, via `--on-mismatch`.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use sui_types::base_types::{ObjectDigest, ObjectID, ObjectRef, SequenceNumber};
+use sui_types::digests::{TransactionDigest, TransactionEffectsDigest};
+use sui_types::effects::{TransactionEffects, TransactionEffectsAPI};
+use sui_types::gas::GasCostSummary;
+
+/// What an operator asked the tool to do when recomputed effects don't match
+/// the checkpoint's certified effects digest.
+#[derive(Copy, Clone, clap::ValueEnum)]
+#[clap(rename_all = "kebab-case")]
+pub enum OnMismatch {
+    /// Stop immediately, as the tool always did before this existed.
+    Abort,
+    /// Log the divergence and keep replaying.
+    Continue,
+    /// Log the divergence, keep replaying, and remember it for a final
+    /// JSON report.
+    Collect,
+}
+
+/// Per-object and gas-level differences between the recomputed and
+/// checkpoint-certified effects for one transaction.
+#[derive(serde::Serialize)]
+pub struct EffectsDiff {
+    pub checkpoint_seq: u64,
+    pub tx_digest: TransactionDigest,
+    pub expected_effects_digest: TransactionEffectsDigest,
+    pub actual_effects_digest: TransactionEffectsDigest,
+    pub created_mismatch: Vec<ObjectRefMismatch>,
+    pub mutated_mismatch: Vec<ObjectRefMismatch>,
+    pub deleted_mismatch: Vec<ObjectRefMismatch>,
+    pub wrapped_mismatch: Vec<ObjectRefMismatch>,
+    pub gas_used_delta: GasUsedDelta,
+    pub status_mismatch: bool,
+}
+
+/// The expected-vs-actual (version, digest) for one object ID, for any ID
+/// that appears in one side's effects but not the other, or appears in
+/// both but landed on a different version/digest. This is what actually
+/// catches the common divergence shape: the same object mutated in both
+/// runs but ending up with different content, which a same-ID-set
+/// comparison can't see.
+#[derive(serde::Serialize)]
+pub struct ObjectRefMismatch {
+    pub object_id: ObjectID,
+    pub expected: Option<(SequenceNumber, ObjectDigest)>,
+    pub actual: Option<(SequenceNumber, ObjectDigest)>,
+}
+
+#[derive(serde::Serialize)]
+pub struct GasUsedDelta {
+    pub expected: GasCostSummary,
+    pub actual: GasCostSummary,
+}
+
+/// Computes a structured diff between `expected` (from the checkpoint) and
+/// `actual` (recomputed) effects for one transaction. Should only be called
+/// once `actual.digest() != expected_effects_digest` has already been
+/// established; it doesn't re-check that itself.
+pub fn diff_effects(
+    checkpoint_seq: u64,
+    tx_digest: TransactionDigest,
+    expected_effects_digest: TransactionEffectsDigest,
+    expected: &TransactionEffects,
+    actual: &TransactionEffects,
+) -> EffectsDiff {
+    let owned_refs = |refs: &[(ObjectRef, sui_types::base_types::Owner)]| -> BTreeMap<ObjectID, (SequenceNumber, ObjectDigest)> {
+        refs.iter().map(|(oref, _)| (oref.0, (oref.1, oref.2))).collect()
+    };
+    let bare_refs = |refs: &[ObjectRef]| -> BTreeMap<ObjectID, (SequenceNumber, ObjectDigest)> {
+        refs.iter().map(|oref| (oref.0, (oref.1, oref.2))).collect()
+    };
+
+    EffectsDiff {
+        checkpoint_seq,
+        tx_digest,
+        expected_effects_digest,
+        actual_effects_digest: actual.digest(),
+        created_mismatch: diff_object_refs(owned_refs(expected.created()), owned_refs(actual.created())),
+        mutated_mismatch: diff_object_refs(owned_refs(expected.mutated()), owned_refs(actual.mutated())),
+        deleted_mismatch: diff_object_refs(bare_refs(expected.deleted()), bare_refs(actual.deleted())),
+        wrapped_mismatch: diff_object_refs(bare_refs(expected.wrapped()), bare_refs(actual.wrapped())),
+        gas_used_delta: GasUsedDelta {
+            expected: expected.gas_cost_summary().clone(),
+            actual: actual.gas_cost_summary().clone(),
+        },
+        status_mismatch: expected.status() != actual.status(),
+    }
+}
+
+/// Diffs two ID -> (version, digest) maps, reporting every ID whose
+/// (version, digest) differs between `expected` and `actual` — including
+/// IDs present on only one side (expected/actual `None`) and, critically,
+/// IDs present on both sides that resolved to a different version or
+/// digest, which is what a same-ID-set comparison would miss entirely.
+fn diff_object_refs(
+    expected: BTreeMap<ObjectID, (SequenceNumber, ObjectDigest)>,
+    actual: BTreeMap<ObjectID, (SequenceNumber, ObjectDigest)>,
+) -> Vec<ObjectRefMismatch> {
+    let ids: BTreeSet<ObjectID> = expected.keys().chain(actual.keys()).copied().collect();
+    ids.into_iter()
+        .filter_map(|object_id| {
+            let expected_ref = expected.get(&object_id).copied();
+            let actual_ref = actual.get(&object_id).copied();
+            (expected_ref != actual_ref).then_some(ObjectRefMismatch {
+                object_id,
+                expected: expected_ref,
+                actual: actual_ref,
+            })
+        })
+        .collect()
+}
+
+/// A mismatch whose checkpoint-certified effects couldn't be loaded (e.g.
+/// pruned from `sw_state.store`), so [`diff_effects`] could never run and
+/// the full per-object diff is unavailable. Recorded by digest alone so the
+/// report's mismatch count isn't silently short of reality.
+#[derive(serde::Serialize)]
+pub struct UndiagnosedMismatch {
+    pub checkpoint_seq: u64,
+    pub tx_digest: TransactionDigest,
+    pub expected_effects_digest: TransactionEffectsDigest,
+    pub actual_effects_digest: TransactionEffectsDigest,
+}
+
+/// Accumulates divergences across a whole replay run for `--on-mismatch
+/// collect`, dumped to a JSON report file at the end.
+#[derive(Default, serde::Serialize)]
+pub struct MismatchReport {
+    pub mismatches: Vec<EffectsDiff>,
+    pub undiagnosed: Vec<UndiagnosedMismatch>,
+}
+
+impl MismatchReport {
+    pub fn record(&mut self, diff: EffectsDiff) {
+        self.mismatches.push(diff);
+    }
+
+    pub fn record_undiagnosed(&mut self, mismatch: UndiagnosedMismatch) {
+        self.undiagnosed.push(mismatch);
+    }
+
+    /// Total mismatches seen, diagnosed or not — what an operator actually
+    /// wants to know before deciding whether the run is trustworthy.
+    pub fn total_mismatches(&self) -> usize {
+        self.mismatches.len() + self.undiagnosed.len()
+    }
+
+    pub fn write_to_file(&self, path: &std::path::Path) -> std::io::Result<()> {
+        let json = serde_json::to_string_pretty(self).expect("report is always serializable");
+        std::fs::write(path, json)
+    }
+}
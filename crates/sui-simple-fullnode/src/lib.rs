@@ -0,0 +1,158 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use sui_config::NodeConfig;
+use sui_core::authority::authority_per_epoch_store::AuthorityPerEpochStore;
+use sui_core::authority::epoch_start_configuration::EpochStartConfiguration;
+use sui_core::checkpoints::CheckpointStore;
+use sui_core::metrics::ReplayMetrics;
+use sui_storage::IndexStore;
+use sui_types::committee::Committee;
+
+pub mod block_stm;
+pub mod diagnostics;
+pub mod metrics;
+pub mod parallel;
+pub mod pipeline;
+pub mod snapshot;
+pub mod store;
+pub mod verify;
+
+pub use diagnostics::{diff_effects, EffectsDiff, MismatchReport, OnMismatch, UndiagnosedMismatch};
+pub use metrics::{format_throughput, start_metrics_server, ProgressReporter, ReplayPrometheusMetrics};
+pub use parallel::{execute_checkpoint_parallel, SpeculativeResult};
+pub use pipeline::SequencedCheckpoint;
+pub use snapshot::ReplaySnapshot;
+pub use store::{
+    AnyReplayStore, CacheUpdatePolicy, MemoryBackedStore, ReplayStore, RocksDbBackedStore, SpeculativeStore,
+    WriteThroughCache,
+};
+pub use verify::{verify_checkpoint_summary, verify_committee_transition, VerificationFailure};
+
+/// Everything the sequencing half of the replay tool needs to track as it
+/// syncs checkpoints from the network: the checkpoint index, the raw
+/// transaction/object store, the current epoch's execution context, and the
+/// metrics handle shared with the execution half.
+pub struct SequenceWorkerState {
+    pub store: Arc<IndexStore>,
+    pub checkpoint_store: Arc<CheckpointStore>,
+    pub epoch_store: Arc<AuthorityPerEpochStore>,
+    pub metrics: Arc<ReplayMetrics>,
+}
+
+impl SequenceWorkerState {
+    pub async fn new(config: &NodeConfig) -> Self {
+        let store = Arc::new(IndexStore::open(config.db_path().join("indexes")));
+        let checkpoint_store = Arc::new(CheckpointStore::open(config.db_path().join("checkpoints")));
+        let metrics = Arc::new(ReplayMetrics::new());
+        let epoch_store = AuthorityPerEpochStore::new_at_genesis(config, metrics.clone());
+        Self {
+            store,
+            checkpoint_store,
+            epoch_store,
+            metrics,
+        }
+    }
+
+    /// Extension point for syncing checkpoint summaries, contents, and
+    /// transaction blocks up to `watermark` into `checkpoint_store`/`store`.
+    /// This binary has no implementation of it: the body panics.
+    ///
+    /// Takes `&self`: `checkpoint_store` and `store` are backed by RocksDB
+    /// column families, which tolerate concurrent writers, so this can be
+    /// driven from the sequencing task while the execution task reads the
+    /// same handles.
+    ///
+    /// Callers must check [`checkpoint_sync_supported`] before reaching
+    /// this: this tool has no network sync client of its own (it replays
+    /// against a `checkpoint_store` populated out-of-band, e.g. by a full
+    /// fullnode sharing the same on-disk store), so this is unreachable in
+    /// practice and exists only as a documented extension point.
+    pub async fn handle_download(&self, watermark: u64, config: &NodeConfig) {
+        let _ = (watermark, config);
+        unreachable!(
+            "checkpoint sync implementation lives in the full fullnode sync client; \
+             callers must gate on checkpoint_sync_supported() before calling handle_download"
+        )
+    }
+
+    /// Reconstructs the epoch store a [`ReplaySnapshot`] was taken under,
+    /// landing directly on `snapshot.epoch` instead of the genesis epoch
+    /// `self.epoch_store` starts at.
+    ///
+    /// `new_at_next_epoch` only ever advances one epoch at a time (every
+    /// other call site asserts `self.epoch() + 1 == next_epoch` before
+    /// calling it), so resuming many epochs past genesis needs one hop per
+    /// epoch boundary, not a single jump straight to the snapshot's
+    /// committee. Each intermediate boundary's committee comes from the
+    /// already-certified `end_of_epoch_data` on that epoch's last
+    /// checkpoint, which `checkpoint_store` must hold for resume to work
+    /// at all. Intermediate hops get a placeholder `EpochStartConfiguration`
+    /// instead of the real one: reconstructing that would mean replaying
+    /// every historical epoch's system-state object, exactly what
+    /// snapshotting exists to avoid. Only the final hop's configuration is
+    /// ever read afterwards (protocol config, move VM, and gas price all
+    /// come from it), so that one uses the snapshot's own copy.
+    pub fn epoch_store_for_resume(
+        &self,
+        config: &NodeConfig,
+        snapshot: &ReplaySnapshot,
+    ) -> Arc<AuthorityPerEpochStore> {
+        let mut epoch_store = self.epoch_store.clone();
+        while epoch_store.epoch() < snapshot.epoch {
+            let next_epoch = epoch_store.epoch() + 1;
+            let Some(last_checkpoint) = self
+                .checkpoint_store
+                .get_epoch_last_checkpoint(epoch_store.epoch())
+                .expect("Error loading last checkpoint for epoch")
+            else {
+                eprintln!(
+                    "Epoch {}'s boundary checkpoint isn't in the local checkpoint store, so \
+                     resuming past it into epoch {next_epoch} isn't possible (this binary has \
+                     no checkpoint sync client built in; populate `checkpoint_store` \
+                     out-of-band, e.g. by pointing --config-path at a node that shares its \
+                     on-disk store, and confirm it hasn't been pruned past this snapshot's epoch).",
+                    epoch_store.epoch()
+                );
+                std::process::exit(1);
+            };
+            let (_, summary) = last_checkpoint.into_summary_and_sequence();
+            let end_of_epoch_data = summary
+                .end_of_epoch_data
+                .expect("Last checkpoint of an epoch always carries end_of_epoch_data");
+            let next_committee = Committee::new(
+                next_epoch,
+                end_of_epoch_data.next_epoch_committee.into_iter().collect::<BTreeMap<_, _>>(),
+            );
+            let epoch_start_configuration = if next_epoch == snapshot.epoch {
+                snapshot.epoch_start_configuration.clone()
+            } else {
+                EpochStartConfiguration::new_for_testing()
+            };
+            epoch_store = epoch_store.new_at_next_epoch(
+                config.protocol_public_key(),
+                next_committee,
+                epoch_start_configuration,
+                self.store.clone(),
+                &config.expensive_safety_check_config,
+            );
+        }
+        assert_eq!(
+            epoch_store.protocol_config().version.as_u64(),
+            snapshot.protocol_version,
+            "resumed epoch store's protocol version doesn't match the snapshot"
+        );
+        epoch_store
+    }
+}
+
+/// Whether this binary can actively fetch checkpoints it doesn't already
+/// have. It can't: it's a replay tool, not a sync client, and relies on
+/// `checkpoint_store` already holding everything it needs (e.g. populated
+/// by a full fullnode sharing the same on-disk store). `--download` and a
+/// cold `--execute` both need this, so callers check it up front and fail
+/// with an explicit, actionable error instead of discovering the gap via a
+/// panic the first time the pipeline reaches a missing checkpoint.
+pub fn checkpoint_sync_supported() -> bool {
+    false
+}
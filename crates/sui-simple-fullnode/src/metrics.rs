@@ -0,0 +1,190 @@
+//! Replay progress and throughput reporting: a human-readable rolling
+//! summary on stdout, and an optional Prometheus `/metrics` endpoint for
+//! long replays that need to be monitored rather than tailed.
+
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+
+use prometheus::{Gauge, IntCounter, IntGauge, Registry};
+
+/// Formats a transactions-per-second rate the way an operator reads it at
+/// a glance, e.g. `12.3 Ktx/s` rather than `12345.2`.
+pub fn format_throughput(tx_per_sec: f64) -> String {
+    if tx_per_sec >= 1_000_000.0 {
+        format!("{:.1} Mtx/s", tx_per_sec / 1_000_000.0)
+    } else if tx_per_sec >= 1_000.0 {
+        format!("{:.1} Ktx/s", tx_per_sec / 1_000.0)
+    } else {
+        format!("{tx_per_sec:.1} tx/s")
+    }
+}
+
+fn format_elapsed(elapsed: Duration) -> String {
+    let secs = elapsed.as_secs();
+    format!("{:02}:{:02}:{:02}", secs / 3600, (secs % 3600) / 60, secs % 60)
+}
+
+/// Formats a cumulative byte count the way an operator reads it at a
+/// glance, e.g. `1.2 GB` rather than `1234567890`.
+fn format_bytes(bytes: u64) -> String {
+    if bytes >= 1_000_000_000 {
+        format!("{:.1} GB", bytes as f64 / 1_000_000_000.0)
+    } else if bytes >= 1_000_000 {
+        format!("{:.1} MB", bytes as f64 / 1_000_000.0)
+    } else if bytes >= 1_000 {
+        format!("{:.1} KB", bytes as f64 / 1_000.0)
+    } else {
+        format!("{bytes} B")
+    }
+}
+
+/// Tracks replay throughput over a rolling window, rather than a
+/// cumulative average since process start (which washes out slowdowns),
+/// and prints a human-readable summary every `--progress-interval-secs`.
+pub struct ProgressReporter {
+    run_start: Instant,
+    window_start: Instant,
+    window_checkpoints: u64,
+    window_tx: u64,
+    total_bytes: u64,
+}
+
+impl ProgressReporter {
+    pub fn new(now: Instant) -> Self {
+        Self {
+            run_start: now,
+            window_start: now,
+            window_checkpoints: 0,
+            window_tx: 0,
+            total_bytes: 0,
+        }
+    }
+
+    /// Records one more replayed checkpoint: its transaction count, and
+    /// `bytes`, the serialized size of the transactions and written
+    /// objects it actually processed (not just its `CheckpointContents`
+    /// digest pairs), towards the running "bytes processed" total reported
+    /// alongside throughput.
+    pub fn record_checkpoint(&mut self, num_tx: usize, bytes: u64) {
+        self.window_checkpoints += 1;
+        self.window_tx += num_tx as u64;
+        self.total_bytes += bytes;
+    }
+
+    /// Whether at least `interval` has elapsed since the last report, i.e.
+    /// whether it's worth the caller computing `store_object_count` (a full
+    /// store scan on the disk-backed backend) to pass to `report`.
+    pub fn due(&self, interval: Duration, now: Instant) -> bool {
+        now.duration_since(self.window_start) >= interval
+    }
+
+    /// Prints a progress line and resets the rolling window. Returns the
+    /// window's tx/s and checkpoints/s, for callers also exporting them as
+    /// gauges. Call only when [`Self::due`] returns true.
+    pub fn report(&mut self, checkpoint_seq: u64, store_object_count: usize, now: Instant) -> (f64, f64) {
+        let elapsed = now.duration_since(self.window_start);
+        let elapsed_secs = elapsed.as_secs_f64().max(0.001);
+        let tx_per_sec = self.window_tx as f64 / elapsed_secs;
+        let checkpoints_per_sec = self.window_checkpoints as f64 / elapsed_secs;
+        println!(
+            "checkpoint {checkpoint_seq}: {} ({checkpoints_per_sec:.1} checkpoints/s), \
+             {store_object_count} objects in store, {} processed, {} elapsed",
+            format_throughput(tx_per_sec),
+            format_bytes(self.total_bytes),
+            format_elapsed(now.duration_since(self.run_start)),
+        );
+        self.window_start = now;
+        self.window_checkpoints = 0;
+        self.window_tx = 0;
+        (tx_per_sec, checkpoints_per_sec)
+    }
+}
+
+/// Prometheus counters for a running replay, registered into `registry` and
+/// served over `--metrics-address` by [`start_metrics_server`]. Separate
+/// from `sui_core::metrics::ReplayMetrics`, which tracks execution-engine
+/// internals rather than the replay loop itself.
+pub struct ReplayPrometheusMetrics {
+    pub checkpoints_per_sec: Gauge,
+    pub tx_per_sec: Gauge,
+    pub current_epoch: IntGauge,
+    pub store_object_count: IntGauge,
+    pub mismatch_count: IntCounter,
+}
+
+impl ReplayPrometheusMetrics {
+    pub fn new(registry: &Registry) -> Self {
+        let checkpoints_per_sec =
+            Gauge::new("replay_checkpoints_per_sec", "Checkpoints processed per second").unwrap();
+        let tx_per_sec = Gauge::new("replay_tx_per_sec", "Transactions executed per second").unwrap();
+        let current_epoch = IntGauge::new("replay_current_epoch", "Epoch currently being replayed").unwrap();
+        let store_object_count =
+            IntGauge::new("replay_store_object_count", "Live objects in the replay store").unwrap();
+        let mismatch_count =
+            IntCounter::new("replay_mismatch_count", "Effects digest mismatches seen so far").unwrap();
+
+        registry.register(Box::new(checkpoints_per_sec.clone())).unwrap();
+        registry.register(Box::new(tx_per_sec.clone())).unwrap();
+        registry.register(Box::new(current_epoch.clone())).unwrap();
+        registry.register(Box::new(store_object_count.clone())).unwrap();
+        registry.register(Box::new(mismatch_count.clone())).unwrap();
+
+        Self {
+            checkpoints_per_sec,
+            tx_per_sec,
+            current_epoch,
+            store_object_count,
+            mismatch_count,
+        }
+    }
+}
+
+/// Starts the Prometheus `/metrics` endpoint in the background, over the
+/// same `mysten_metrics` HTTP server every other Sui binary serves its
+/// registry through, so `--metrics-address` behaves like the real
+/// fullnode's.
+pub fn start_metrics_server(address: SocketAddr, registry: &Registry) {
+    mysten_metrics::start_prometheus_server(address, registry);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_throughput_picks_the_right_unit() {
+        assert_eq!(format_throughput(42.0), "42.0 tx/s");
+        assert_eq!(format_throughput(1_234.0), "1.2 Ktx/s");
+        assert_eq!(format_throughput(2_500_000.0), "2.5 Mtx/s");
+    }
+
+    #[test]
+    fn format_bytes_picks_the_right_unit() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1_500), "1.5 KB");
+        assert_eq!(format_bytes(2_500_000), "2.5 MB");
+        assert_eq!(format_bytes(3_200_000_000), "3.2 GB");
+    }
+
+    #[test]
+    fn report_computes_window_rate_and_resets_it() {
+        let start = Instant::now();
+        let mut progress = ProgressReporter::new(start);
+        assert!(!progress.due(Duration::from_secs(10), start + Duration::from_secs(5)));
+        assert!(progress.due(Duration::from_secs(10), start + Duration::from_secs(10)));
+
+        progress.record_checkpoint(100, 1_000);
+        progress.record_checkpoint(100, 1_000);
+        let (tx_per_sec, checkpoints_per_sec) = progress.report(7, 0, start + Duration::from_secs(10));
+        assert_eq!(tx_per_sec, 20.0);
+        assert_eq!(checkpoints_per_sec, 0.2);
+
+        // The window resets on report, so an immediately following window
+        // with no new checkpoints reports a zero rate rather than carrying
+        // the previous window's totals forward.
+        let (tx_per_sec, checkpoints_per_sec) =
+            progress.report(7, 0, start + Duration::from_secs(20));
+        assert_eq!(tx_per_sec, 0.0);
+        assert_eq!(checkpoints_per_sec, 0.0);
+    }
+}
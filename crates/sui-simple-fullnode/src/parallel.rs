@@ -0,0 +1,230 @@
+//! Drives the [`block_stm`](crate::block_stm) scheduler to execute a
+//! checkpoint's transactions across a thread pool while preserving the
+//! invariant that the result is identical to running them one at a time in
+//! checkpoint order.
+
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use sui_types::base_types::{ObjectID, ObjectRef};
+use sui_types::object::Object;
+
+use crate::block_stm::{validate_read_set, MVMemory, ReadSet, Scheduler, Task};
+
+/// What a single speculative run of one transaction produced.
+pub struct SpeculativeResult<E> {
+    pub effects: E,
+    pub read_set: ReadSet,
+    /// `None` write means the object was deleted.
+    pub writes: Vec<(ObjectID, Option<(ObjectRef, Object)>)>,
+    /// Set if this run observed an aborted-but-not-yet-re-executed write;
+    /// such a run can never validate and is retried unconditionally.
+    pub saw_estimate: bool,
+}
+
+/// Runs `execute(txn_idx, incarnation)` for every transaction in
+/// `declared_inputs`'s order across `num_threads` workers, validating each
+/// result's read-set against [`MVMemory`] and retrying aborted transactions
+/// with a bumped incarnation, until every transaction has committed a valid
+/// result. Returns results in `txn_idx` order, ready to be applied to the
+/// base store sequentially.
+pub fn execute_checkpoint_parallel<E, F>(
+    num_threads: usize,
+    declared_inputs: Vec<HashSet<ObjectID>>,
+    mv_memory: &MVMemory<(ObjectRef, Object)>,
+    execute: F,
+) -> Vec<SpeculativeResult<E>>
+where
+    E: Send,
+    F: Fn(usize, usize) -> SpeculativeResult<E> + Sync,
+{
+    let num_txns = declared_inputs.len();
+    let scheduler = Scheduler::new(declared_inputs);
+    let remaining = AtomicUsize::new(num_txns);
+    // `results[txn_idx]` is `Some` exactly while `txn_idx` is committed.
+    // There is no separate `committed` flag: a slot going back to `None` via
+    // `decommit` below *is* what un-commits a transaction.
+    let results: Vec<Mutex<Option<SpeculativeResult<E>>>> = (0..num_txns).map(|_| Mutex::new(None)).collect();
+
+    // Pulls an already-committed transaction back into `Execute`. Marks its
+    // writes as estimates *before* requeuing it: requeuing immediately makes
+    // `txn_idx` eligible for re-dispatch with a bumped incarnation, and if
+    // that happened before the estimate mark landed, a second worker could
+    // record the new incarnation's writes only for this thread's
+    // `mark_estimate` (using the old incarnation's write-set) to stomp them
+    // back to `Estimate` right after.
+    let decommit = |txn_idx: usize, slot: &mut Option<SpeculativeResult<E>>| {
+        let result = slot.take().expect("decommit called on an uncommitted slot");
+        let written: HashSet<ObjectID> = result.writes.iter().map(|(id, _)| *id).collect();
+        mv_memory.mark_estimate(txn_idx, &written);
+        scheduler.abort_and_requeue(txn_idx);
+        remaining.fetch_add(1, Ordering::SeqCst);
+    };
+
+    // Executes `txn_idx` once and either commits it (returning `true`) or
+    // marks it estimate-and-requeues it (returning `false`). On commit,
+    // sweeps every higher-index transaction that's currently committed and
+    // decommits any whose read-set this new write just invalidated: a
+    // higher index can finish and validate against the base store (or an
+    // older version) before a lower index's write actually lands, and that
+    // stale commit has to be caught, not left to stand.
+    let try_commit = |txn_idx: usize, incarnation: usize| -> bool {
+        let speculative = execute(txn_idx, incarnation);
+        for (id, value) in &speculative.writes {
+            mv_memory.record_write(*id, txn_idx, incarnation, value.clone());
+        }
+
+        let valid = !speculative.saw_estimate && validate_read_set(mv_memory, txn_idx, &speculative.read_set);
+        if !valid {
+            let written: HashSet<ObjectID> = speculative.writes.iter().map(|(id, _)| *id).collect();
+            mv_memory.mark_estimate(txn_idx, &written);
+            scheduler.abort_and_requeue(txn_idx);
+            return false;
+        }
+
+        let mut slot = results[txn_idx].lock().unwrap();
+        if slot.is_some() {
+            // Someone else already committed this index (e.g. right after
+            // decommitting it); drop this redundant result.
+            return true;
+        }
+        *slot = Some(speculative);
+        drop(slot);
+        remaining.fetch_sub(1, Ordering::SeqCst);
+
+        for later_idx in (txn_idx + 1)..num_txns {
+            let mut later_slot = results[later_idx].lock().unwrap();
+            if let Some(later_result) = later_slot.as_ref() {
+                if !validate_read_set(mv_memory, later_idx, &later_result.read_set) {
+                    decommit(later_idx, &mut later_slot);
+                }
+            }
+        }
+        true
+    };
+
+    std::thread::scope(|scope| {
+        for _ in 0..num_threads.max(1) {
+            scope.spawn(|| loop {
+                if remaining.load(Ordering::SeqCst) == 0 {
+                    return;
+                }
+                let (txn_idx, incarnation) = match scheduler.next_task() {
+                    Task::Execute { txn_idx, incarnation } => (txn_idx, incarnation),
+                    Task::Done => {
+                        std::thread::yield_now();
+                        continue;
+                    }
+                };
+                if results[txn_idx].lock().unwrap().is_some() {
+                    continue;
+                }
+                try_commit(txn_idx, incarnation);
+            });
+        }
+    });
+
+    // The parallel phase is optimistic about termination too: a worker can
+    // observe `remaining == 0` and return while another thread's commit is
+    // mid-sweep and about to decommit a slot, which would otherwise leave
+    // that transaction permanently uncommitted with no worker left to pick
+    // it back up. Finish any such slot here, single-threaded, where a
+    // decommit can't race the cleanup itself.
+    loop {
+        let Some(txn_idx) = (0..num_txns).find(|&i| results[i].lock().unwrap().is_none()) else {
+            break;
+        };
+        loop {
+            let incarnation = scheduler.incarnation(txn_idx);
+            if try_commit(txn_idx, incarnation) {
+                break;
+            }
+        }
+    }
+
+    results
+        .into_iter()
+        .map(|cell| cell.into_inner().unwrap().expect("every transaction must have committed"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    use sui_types::base_types::SuiAddress;
+    use sui_types::object::Owner;
+
+    use crate::block_stm::ReadResult;
+
+    fn object_with_owner(id: ObjectID, owner: SuiAddress) -> (ObjectRef, Object) {
+        let object = Object::with_id_owner_for_testing(id, owner);
+        (object.compute_object_reference(), object)
+    }
+
+    /// Two transactions sharing one object: txn 1 declares `shared_id` as an
+    /// input and writes a second object whose owner records which version of
+    /// `shared_id` it actually saw. txn 0 is made to run slow, so the
+    /// scheduler's unordered dispatch lets txn 1 race ahead and commit
+    /// against the base store first almost every time; the sweep in
+    /// `try_commit` then has to decommit and re-run txn 1 once txn 0's write
+    /// lands, and the final result has to reflect that re-run, matching what
+    /// sequential, in-order execution would have produced, not the stale
+    /// first pass.
+    #[test]
+    fn conflicting_transactions_resolve_to_sequential_order() {
+        let shared_id = ObjectID::random();
+        let derived_id = ObjectID::random();
+        let owner_from_commit = SuiAddress::random_for_testing_only();
+        let owner_from_base = SuiAddress::random_for_testing_only();
+
+        let declared_inputs = vec![HashSet::new(), HashSet::from([shared_id])];
+        let mv_memory: MVMemory<(ObjectRef, Object)> = MVMemory::new();
+
+        let results = execute_checkpoint_parallel(2, declared_inputs, &mv_memory, |txn_idx, _incarnation| {
+            match txn_idx {
+                0 => {
+                    // Slow down txn 0 so txn 1 gets a real chance to commit
+                    // against the base store before txn 0's write lands.
+                    std::thread::sleep(Duration::from_millis(20));
+                    SpeculativeResult {
+                        effects: (),
+                        read_set: Vec::new(),
+                        writes: vec![(shared_id, Some(object_with_owner(shared_id, owner_from_commit)))],
+                        saw_estimate: false,
+                    }
+                }
+                1 => match mv_memory.read(&shared_id, txn_idx) {
+                    ReadResult::Base => SpeculativeResult {
+                        effects: (),
+                        read_set: vec![(shared_id, None)],
+                        writes: vec![(derived_id, Some(object_with_owner(derived_id, owner_from_base)))],
+                        saw_estimate: false,
+                    },
+                    ReadResult::Version {
+                        txn_idx: writer_idx,
+                        incarnation: writer_incarnation,
+                        ..
+                    } => SpeculativeResult {
+                        effects: (),
+                        read_set: vec![(shared_id, Some((writer_idx, writer_incarnation)))],
+                        writes: vec![(derived_id, Some(object_with_owner(derived_id, owner_from_commit)))],
+                        saw_estimate: false,
+                    },
+                    ReadResult::Estimate { .. } => SpeculativeResult {
+                        effects: (),
+                        read_set: Vec::new(),
+                        writes: Vec::new(),
+                        saw_estimate: true,
+                    },
+                },
+                _ => unreachable!(),
+            }
+        });
+
+        let (_, derived_object) = results[1].writes[0].1.as_ref().expect("derived object was written");
+        assert_eq!(derived_object.owner, Owner::AddressOwner(owner_from_commit));
+    }
+}
@@ -0,0 +1,119 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use sui_config::NodeConfig;
+use sui_types::messages_checkpoint::{CheckpointContents, VerifiedCheckpoint};
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+
+use crate::SequenceWorkerState;
+
+/// How long the sequencing task backs off when it catches up to the
+/// network's current watermark and has nothing left to push.
+const CAUGHT_UP_BACKOFF: Duration = Duration::from_millis(50);
+
+/// One checkpoint's worth of sequenced data, ready for the execution task to
+/// consume. Transaction blocks themselves aren't included here: they're
+/// already indexed in `SequenceWorkerState::store`, so the executor fetches
+/// them itself as it walks `contents`.
+pub struct SequencedCheckpoint {
+    pub sequence_number: u64,
+    pub summary: VerifiedCheckpoint,
+    pub contents: CheckpointContents,
+}
+
+impl SequenceWorkerState {
+    /// Spawns a background task that walks checkpoints from `start_seq`
+    /// onward and pushes each one's summary + contents over a channel
+    /// bounded by `pipeline_depth`, so execution can start without a full
+    /// `--download` pass finishing first. Checkpoints are read from
+    /// `checkpoint_store`; since [`crate::checkpoint_sync_supported`] is
+    /// false, one that isn't there yet is not downloaded: the task backs off
+    /// and retries until an external writer lands it.
+    ///
+    /// Returns the receiving end of that channel, a sender the execution
+    /// task can use to request an on-demand fetch of a checkpoint it has
+    /// raced ahead of (acked once the fetch attempt has finished, so the
+    /// caller can re-read `checkpoint_store` without racing this task; the
+    /// checkpoint may still be absent if the attempt found nothing), and
+    /// the task's `JoinHandle`.
+    pub fn spawn_sequencing_task(
+        self: Arc<Self>,
+        config: NodeConfig,
+        start_seq: u64,
+        pipeline_depth: usize,
+    ) -> (
+        mpsc::Receiver<SequencedCheckpoint>,
+        mpsc::Sender<(u64, oneshot::Sender<()>)>,
+        JoinHandle<()>,
+    ) {
+        let (checkpoint_tx, checkpoint_rx) = mpsc::channel(pipeline_depth);
+        let (fetch_tx, mut fetch_rx) = mpsc::channel::<(u64, oneshot::Sender<()>)>(pipeline_depth);
+
+        let handle = tokio::spawn(async move {
+            let mut next_seq = start_seq;
+            loop {
+                // Service an on-demand fetch request first: the executor
+                // only sends one when it has outrun us, so it's the more
+                // urgent request. Ack only after the fetch attempt has
+                // finished, so the requester can safely re-read
+                // `checkpoint_store` without racing this task.
+                if let Ok((requested_seq, ack)) = fetch_rx.try_recv() {
+                    self.sync_checkpoint(requested_seq, &config).await;
+                    let _ = ack.send(());
+                }
+
+                let Some(summary) = self.sync_checkpoint(next_seq, &config).await else {
+                    tokio::time::sleep(CAUGHT_UP_BACKOFF).await;
+                    continue;
+                };
+                let contents = self
+                    .checkpoint_store
+                    .get_checkpoint_contents(&summary.content_digest)
+                    .expect("Contents must exist")
+                    .expect("Contents must exist");
+
+                let sequenced = SequencedCheckpoint {
+                    sequence_number: next_seq,
+                    summary,
+                    contents,
+                };
+                if checkpoint_tx.send(sequenced).await.is_err() {
+                    // Execution task is gone; nothing left to sequence for.
+                    return;
+                }
+                next_seq += 1;
+            }
+        });
+
+        (checkpoint_rx, fetch_tx, handle)
+    }
+
+    /// Returns `seq`'s summary, downloading it first if it isn't already in
+    /// `checkpoint_store`. Returns `None` if `seq` isn't available yet,
+    /// whether that's because it's beyond the network's current watermark
+    /// or (see [`crate::checkpoint_sync_supported`]) because this binary
+    /// has no sync client and is waiting on an external writer to land it.
+    /// Either way the caller's catch-up backoff is the right response, not
+    /// a panic.
+    async fn sync_checkpoint(
+        &self,
+        seq: u64,
+        config: &NodeConfig,
+    ) -> Option<sui_types::messages_checkpoint::VerifiedCheckpoint> {
+        if let Some(summary) = self
+            .checkpoint_store
+            .get_checkpoint_by_sequence_number(seq)
+            .expect("Cannot get checkpoint")
+        {
+            return Some(summary);
+        }
+        if !crate::checkpoint_sync_supported() {
+            return None;
+        }
+        self.handle_download(seq, config).await;
+        self.checkpoint_store
+            .get_checkpoint_by_sequence_number(seq)
+            .expect("Cannot get checkpoint")
+    }
+}
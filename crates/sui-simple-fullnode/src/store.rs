@@ -0,0 +1,452 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::Path;
+
+use sui_types::base_types::{ObjectID, ObjectRef};
+use sui_types::error::SuiError;
+use sui_types::object::Object;
+use sui_types::storage::ObjectStore;
+use typed_store::rocks::{DBMap, MetricConf};
+use typed_store::traits::Map;
+use typed_store_derive::DBMapUtils;
+
+/// Bridges a `ReplayStore` impl to the `ObjectStore` trait that
+/// `TemporaryStore::new` and `get_sui_system_state` are generic over, so any
+/// backend can be dropped in wherever the rest of the engine expects one.
+macro_rules! impl_object_store_via_replay_store {
+    ($ty:ty $(, $bound:ident)?) => {
+        impl $(<$bound: ReplayStore>)? ObjectStore for $ty {
+            fn get_object(&self, object_id: &ObjectID) -> Result<Option<Object>, SuiError> {
+                Ok(ReplayStore::get_object(self, object_id).map(|(_, obj)| obj))
+            }
+        }
+    };
+}
+
+/// Minimal get/insert/delete interface over the live object set, so the
+/// replay loop can be pointed at either an in-memory map or a disk-backed KV
+/// store without caring which.
+pub trait ReplayStore {
+    fn get_object(&self, id: &ObjectID) -> Option<(ObjectRef, Object)>;
+    fn insert_object(&mut self, id: ObjectID, value: (ObjectRef, Object));
+    fn delete_object(&mut self, id: &ObjectID);
+
+    /// Dumps the full live object set, for `--snapshot-interval`. Not meant
+    /// to be called on a hot path: for the disk-backed store this is a full
+    /// column family scan.
+    fn snapshot_objects(&self) -> HashMap<ObjectID, (ObjectRef, Object)>;
+
+    /// Cheap count of the live object set, safe to poll on a timer (e.g. for
+    /// `--progress-interval-secs`). Unlike `snapshot_objects().len()`, this
+    /// never scans or materializes the object set.
+    fn object_count(&self) -> usize;
+}
+
+/// Keeps every live object resident in memory. Fine for short replays, but
+/// its footprint grows with the full object set and eventually OOMs on long
+/// chain histories.
+#[derive(Default)]
+pub struct MemoryBackedStore {
+    pub objects: HashMap<ObjectID, (ObjectRef, Object)>,
+}
+
+impl MemoryBackedStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl ReplayStore for MemoryBackedStore {
+    fn get_object(&self, id: &ObjectID) -> Option<(ObjectRef, Object)> {
+        self.objects.get(id).cloned()
+    }
+
+    fn insert_object(&mut self, id: ObjectID, value: (ObjectRef, Object)) {
+        self.objects.insert(id, value);
+    }
+
+    fn delete_object(&mut self, id: &ObjectID) {
+        self.objects.remove(id);
+    }
+
+    fn snapshot_objects(&self) -> HashMap<ObjectID, (ObjectRef, Object)> {
+        self.objects.clone()
+    }
+
+    fn object_count(&self) -> usize {
+        self.objects.len()
+    }
+}
+
+/// Disk-backed object store for replays of full chain history, where the
+/// live object set does not fit in RAM. Objects are keyed by `ObjectID`
+/// directly; there is no version history, since replay only ever needs the
+/// current object state.
+#[derive(DBMapUtils)]
+pub struct RocksDbBackedStore {
+    objects: DBMap<ObjectID, (ObjectRef, Object)>,
+    /// Maintained incrementally by `insert_object`/`delete_object` so
+    /// `object_count` never has to scan the column family. Initialized once
+    /// from a one-time scan in `open`, which is the only place this store
+    /// pays that cost.
+    #[default]
+    count: usize,
+}
+
+impl RocksDbBackedStore {
+    pub fn open(path: impl AsRef<Path>) -> Self {
+        let mut store =
+            Self::open_tables_read_write(path.as_ref().to_path_buf(), MetricConf::default(), None, None);
+        store.count = store.objects.safe_iter().count();
+        store
+    }
+}
+
+impl ReplayStore for RocksDbBackedStore {
+    fn get_object(&self, id: &ObjectID) -> Option<(ObjectRef, Object)> {
+        self.objects.get(id).expect("db read should not fail")
+    }
+
+    fn insert_object(&mut self, id: ObjectID, value: (ObjectRef, Object)) {
+        if self.objects.get(&id).expect("db read should not fail").is_none() {
+            self.count += 1;
+        }
+        self.objects
+            .insert(&id, &value)
+            .expect("db write should not fail");
+    }
+
+    fn delete_object(&mut self, id: &ObjectID) {
+        if self.objects.get(id).expect("db read should not fail").is_some() {
+            self.count -= 1;
+        }
+        self.objects.remove(id).expect("db write should not fail");
+    }
+
+    fn snapshot_objects(&self) -> HashMap<ObjectID, (ObjectRef, Object)> {
+        self.objects
+            .safe_iter()
+            .collect::<Result<HashMap<_, _>, _>>()
+            .expect("db scan should not fail")
+    }
+
+    fn object_count(&self) -> usize {
+        self.count
+    }
+}
+
+/// Whether a staged write should overwrite the backing store's value for an
+/// object, or delete it outright.
+#[derive(Clone)]
+pub enum CacheUpdatePolicy {
+    Overwrite((ObjectRef, Object)),
+    Remove,
+}
+
+/// Write-through cache in front of a `ReplayStore` backend. Per-transaction
+/// `written`/`deleted` sets are staged here and only flushed to the backing
+/// store in batches at checkpoint boundaries, so a disk-backed backend is not
+/// hit once per transaction.
+pub struct WriteThroughCache<S: ReplayStore> {
+    backend: S,
+    cache: HashMap<ObjectID, CacheUpdatePolicy>,
+    cache_size: usize,
+}
+
+impl<S: ReplayStore> WriteThroughCache<S> {
+    pub fn new(backend: S, cache_size: usize) -> Self {
+        Self {
+            backend,
+            cache: HashMap::new(),
+            cache_size,
+        }
+    }
+
+    pub fn stage_write(&mut self, id: ObjectID, value: (ObjectRef, Object)) {
+        self.cache.insert(id, CacheUpdatePolicy::Overwrite(value));
+    }
+
+    pub fn stage_delete(&mut self, id: ObjectID) {
+        self.cache.insert(id, CacheUpdatePolicy::Remove);
+    }
+
+    /// Flushes every staged update to the backing store. Called at
+    /// checkpoint boundaries, and whenever the staged set reaches
+    /// `cache_size`.
+    pub fn flush(&mut self) {
+        for (id, policy) in self.cache.drain() {
+            match policy {
+                CacheUpdatePolicy::Overwrite(value) => self.backend.insert_object(id, value),
+                CacheUpdatePolicy::Remove => self.backend.delete_object(&id),
+            }
+        }
+    }
+
+    pub fn maybe_flush(&mut self) {
+        if self.cache.len() >= self.cache_size {
+            self.flush();
+        }
+    }
+}
+
+impl<S: ReplayStore> ReplayStore for WriteThroughCache<S> {
+    fn get_object(&self, id: &ObjectID) -> Option<(ObjectRef, Object)> {
+        match self.cache.get(id) {
+            Some(CacheUpdatePolicy::Overwrite(value)) => Some(value.clone()),
+            Some(CacheUpdatePolicy::Remove) => None,
+            None => self.backend.get_object(id),
+        }
+    }
+
+    fn insert_object(&mut self, id: ObjectID, value: (ObjectRef, Object)) {
+        self.stage_write(id, value);
+        self.maybe_flush();
+    }
+
+    fn delete_object(&mut self, id: &ObjectID) {
+        self.stage_delete(*id);
+        self.maybe_flush();
+    }
+
+    fn snapshot_objects(&self) -> HashMap<ObjectID, (ObjectRef, Object)> {
+        let mut objects = self.backend.snapshot_objects();
+        for (id, policy) in &self.cache {
+            match policy {
+                CacheUpdatePolicy::Overwrite(value) => {
+                    objects.insert(*id, value.clone());
+                }
+                CacheUpdatePolicy::Remove => {
+                    objects.remove(id);
+                }
+            }
+        }
+        objects
+    }
+
+    /// Adjusts the backend's maintained count by the not-yet-flushed staged
+    /// writes, each resolved against the backend with a single-key lookup —
+    /// bounded by `cache_size`, unlike `snapshot_objects` which is bounded by
+    /// the full live object set.
+    fn object_count(&self) -> usize {
+        let mut count = self.backend.object_count();
+        for (id, policy) in &self.cache {
+            let already_in_backend = self.backend.get_object(id).is_some();
+            match policy {
+                CacheUpdatePolicy::Overwrite(_) if !already_in_backend => count += 1,
+                CacheUpdatePolicy::Remove if already_in_backend => count -= 1,
+                _ => {}
+            }
+        }
+        count
+    }
+}
+
+impl_object_store_via_replay_store!(MemoryBackedStore);
+impl_object_store_via_replay_store!(RocksDbBackedStore);
+impl_object_store_via_replay_store!(WriteThroughCache<S>, S);
+
+/// Picks between the in-memory and disk-backed stores at runtime, behind the
+/// `--store-backend` flag, without forcing callers to be generic over the
+/// backend themselves.
+pub enum AnyReplayStore {
+    Memory(WriteThroughCache<MemoryBackedStore>),
+    RocksDb(WriteThroughCache<RocksDbBackedStore>),
+}
+
+impl ReplayStore for AnyReplayStore {
+    fn get_object(&self, id: &ObjectID) -> Option<(ObjectRef, Object)> {
+        match self {
+            AnyReplayStore::Memory(store) => store.get_object(id),
+            AnyReplayStore::RocksDb(store) => store.get_object(id),
+        }
+    }
+
+    fn insert_object(&mut self, id: ObjectID, value: (ObjectRef, Object)) {
+        match self {
+            AnyReplayStore::Memory(store) => store.insert_object(id, value),
+            AnyReplayStore::RocksDb(store) => store.insert_object(id, value),
+        }
+    }
+
+    fn delete_object(&mut self, id: &ObjectID) {
+        match self {
+            AnyReplayStore::Memory(store) => store.delete_object(id),
+            AnyReplayStore::RocksDb(store) => store.delete_object(id),
+        }
+    }
+
+    fn snapshot_objects(&self) -> HashMap<ObjectID, (ObjectRef, Object)> {
+        match self {
+            AnyReplayStore::Memory(store) => store.snapshot_objects(),
+            AnyReplayStore::RocksDb(store) => store.snapshot_objects(),
+        }
+    }
+
+    fn object_count(&self) -> usize {
+        match self {
+            AnyReplayStore::Memory(store) => store.object_count(),
+            AnyReplayStore::RocksDb(store) => store.object_count(),
+        }
+    }
+}
+
+impl AnyReplayStore {
+    /// Flushes any staged writes to the backing store. Call at checkpoint
+    /// boundaries so a crash doesn't lose more than one checkpoint's worth of
+    /// uncommitted objects.
+    pub fn flush(&mut self) {
+        match self {
+            AnyReplayStore::Memory(store) => store.flush(),
+            AnyReplayStore::RocksDb(store) => store.flush(),
+        }
+    }
+}
+
+impl_object_store_via_replay_store!(AnyReplayStore);
+
+/// Read-only view of `base` overlaid with a transaction's in-flight
+/// multi-version state, used by the parallel executor (see
+/// [`crate::block_stm`]). Every `get_object` resolves through `mv` first and
+/// records what it saw, so the caller can validate the read-set afterwards;
+/// only on a miss does it fall through to the committed `base` store.
+pub struct SpeculativeStore<'a, S> {
+    base: &'a S,
+    mv: &'a crate::block_stm::MVMemory<(ObjectRef, Object)>,
+    txn_idx: usize,
+    read_set: RefCell<crate::block_stm::ReadSet>,
+    saw_estimate: std::cell::Cell<bool>,
+}
+
+impl<'a, S: ObjectStore> SpeculativeStore<'a, S> {
+    pub fn new(base: &'a S, mv: &'a crate::block_stm::MVMemory<(ObjectRef, Object)>, txn_idx: usize) -> Self {
+        Self {
+            base,
+            mv,
+            txn_idx,
+            read_set: RefCell::new(Vec::new()),
+            saw_estimate: std::cell::Cell::new(false),
+        }
+    }
+
+    /// Whether this execution observed an in-progress (aborted-and-not-yet-
+    /// re-executed) write. If so, its result must be treated as invalid
+    /// regardless of how the read-set from `into_read_set` validates.
+    pub fn saw_estimate(&self) -> bool {
+        self.saw_estimate.get()
+    }
+
+    pub fn into_read_set(self) -> crate::block_stm::ReadSet {
+        self.read_set.into_inner()
+    }
+}
+
+impl<'a, S: ObjectStore> ObjectStore for SpeculativeStore<'a, S> {
+    fn get_object(&self, object_id: &ObjectID) -> Result<Option<Object>, SuiError> {
+        use crate::block_stm::ReadResult;
+        match self.mv.read(object_id, self.txn_idx) {
+            ReadResult::Base => {
+                self.read_set.borrow_mut().push((*object_id, None));
+                self.base.get_object(object_id)
+            }
+            ReadResult::Version {
+                txn_idx,
+                incarnation,
+                value,
+            } => {
+                self.read_set
+                    .borrow_mut()
+                    .push((*object_id, Some((txn_idx, incarnation))));
+                Ok(value.map(|(_, obj)| obj))
+            }
+            ReadResult::Estimate { .. } => {
+                self.saw_estimate.set(true);
+                self.base.get_object(object_id)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sui_types::base_types::SuiAddress;
+
+    fn object_with_ref() -> (ObjectID, (ObjectRef, Object)) {
+        let object = Object::with_id_owner_for_testing(ObjectID::random(), SuiAddress::random_for_testing_only());
+        let object_ref = object.compute_object_reference();
+        (object.id(), (object_ref, object))
+    }
+
+    #[test]
+    fn staged_write_resolves_over_an_unflushed_backend_entry() {
+        let mut cache = WriteThroughCache::new(MemoryBackedStore::new(), 10);
+        let (id, value) = object_with_ref();
+        cache.insert_object(id, value.clone());
+
+        // Not flushed yet: the backend doesn't have it, only the cache does.
+        assert!(cache.backend.get_object(&id).is_none());
+        assert_eq!(cache.get_object(&id), Some(value));
+    }
+
+    #[test]
+    fn staged_delete_hides_a_backend_entry_until_flushed() {
+        let mut cache = WriteThroughCache::new(MemoryBackedStore::new(), 10);
+        let (id, value) = object_with_ref();
+        cache.backend.insert_object(id, value);
+
+        cache.delete_object(&id);
+        assert!(cache.get_object(&id).is_none());
+        // Still present in the backend: the delete is staged, not flushed.
+        assert!(cache.backend.get_object(&id).is_some());
+
+        cache.flush();
+        assert!(cache.backend.get_object(&id).is_none());
+    }
+
+    #[test]
+    fn flush_drains_staged_writes_into_the_backend() {
+        let mut cache = WriteThroughCache::new(MemoryBackedStore::new(), 10);
+        let (id, value) = object_with_ref();
+        cache.insert_object(id, value.clone());
+
+        cache.flush();
+        assert_eq!(cache.backend.get_object(&id), Some(value));
+        assert_eq!(cache.object_count(), 1);
+    }
+
+    #[test]
+    fn maybe_flush_only_flushes_once_cache_size_is_reached() {
+        let mut cache = WriteThroughCache::new(MemoryBackedStore::new(), 2);
+        let (id_a, value_a) = object_with_ref();
+        let (id_b, value_b) = object_with_ref();
+
+        cache.stage_write(id_a, value_a);
+        cache.maybe_flush();
+        assert!(cache.backend.get_object(&id_a).is_none(), "below cache_size, shouldn't flush yet");
+
+        cache.stage_write(id_b, value_b);
+        cache.maybe_flush();
+        assert!(cache.backend.get_object(&id_a).is_some(), "cache_size reached, should flush");
+        assert!(cache.backend.get_object(&id_b).is_some());
+    }
+
+    #[test]
+    fn object_count_accounts_for_unflushed_writes_and_deletes() {
+        let mut cache = WriteThroughCache::new(MemoryBackedStore::new(), 10);
+        let (id_a, value_a) = object_with_ref();
+        let (id_b, value_b) = object_with_ref();
+        cache.backend.insert_object(id_a, value_a);
+
+        // A new staged write (not yet in the backend) increases the count...
+        cache.insert_object(id_b, value_b.clone());
+        assert_eq!(cache.object_count(), 2);
+
+        // ...overwriting an existing id doesn't double-count it...
+        cache.insert_object(id_a, value_b);
+        assert_eq!(cache.object_count(), 2);
+
+        // ...and staging a delete of an id the backend still holds drops it.
+        cache.delete_object(&id_a);
+        assert_eq!(cache.object_count(), 1);
+    }
+}
@@ -0,0 +1,106 @@
+//! Trust-minimized verification of replayed checkpoint data. With
+//! `--verify-signatures`, the replay loop stops trusting `checkpoint_store`'s
+//! contents outright and instead checks the validators' aggregated BLS
+//! signature over each checkpoint summary against the current epoch
+//! committee, and cross-checks committee rotation at epoch boundaries
+//! against what the signed checkpoint claims.
+
+use sui_types::committee::Committee;
+use sui_types::error::SuiError;
+use sui_types::messages_checkpoint::{CheckpointSummary, VerifiedCheckpoint};
+
+/// A checkpoint whose aggregated signature didn't verify against `committee`,
+/// or whose signed `next_epoch_committee` didn't match what replay derived.
+#[derive(Debug)]
+pub struct VerificationFailure {
+    pub checkpoint_seq: u64,
+    pub reason: String,
+}
+
+/// Verifies `checkpoint`'s certified aggregate signature against
+/// `committee`. `committee` must be the committee of the epoch the
+/// checkpoint was produced in, obtained from `epoch_store`.
+pub fn verify_checkpoint_summary(
+    checkpoint: &VerifiedCheckpoint,
+    committee: &Committee,
+) -> Result<(), VerificationFailure> {
+    checkpoint
+        .auth_sig()
+        .verify_secure(
+            checkpoint.data(),
+            sui_types::intent::Intent::default().with_scope(sui_types::intent::IntentScope::CheckpointSummary),
+            committee,
+        )
+        .map_err(|err: SuiError| VerificationFailure {
+            checkpoint_seq: checkpoint.sequence_number(),
+            reason: format!("aggregate signature does not verify against epoch {}: {err}", committee.epoch()),
+        })
+}
+
+/// At an `end_of_epoch_data` boundary, checks that the signed
+/// `next_epoch_committee` matches the committee replay derives from the
+/// post-execution system state. A mismatch means the replayed system state
+/// diverged from what the network actually certified.
+pub fn verify_committee_transition(
+    summary: &CheckpointSummary,
+    derived_next_committee: &Committee,
+) -> Result<(), VerificationFailure> {
+    let signed_next_committee = summary
+        .end_of_epoch_data
+        .as_ref()
+        .expect("only called at end-of-epoch boundaries")
+        .next_epoch_committee
+        .clone();
+
+    let signed_next_committee = Committee::new(derived_next_committee.epoch(), signed_next_committee.into_iter().collect())
+        .map_err(|err| VerificationFailure {
+            checkpoint_seq: summary.sequence_number,
+            reason: format!("signed next_epoch_committee is malformed: {err}"),
+        })?;
+
+    if &signed_next_committee != derived_next_committee {
+        return Err(VerificationFailure {
+            checkpoint_seq: summary.sequence_number,
+            reason: "signed next_epoch_committee does not match the committee derived from \
+                     new_epoch_start_state.get_sui_committee()"
+                .to_string(),
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sui_types::messages_checkpoint::{CheckpointSummary, EndOfEpochData};
+
+    fn summary_with_next_committee(committee: &Committee) -> CheckpointSummary {
+        CheckpointSummary {
+            end_of_epoch_data: Some(EndOfEpochData {
+                next_epoch_committee: committee.voting_rights.clone().into_iter().collect(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn committee_transition_succeeds_when_signed_committee_matches() {
+        let (committee, _keys) = Committee::new_simple_test_committee();
+        let summary = summary_with_next_committee(&committee);
+        assert!(verify_committee_transition(&summary, &committee).is_ok());
+    }
+
+    #[test]
+    fn committee_transition_fails_when_signed_committee_diverges() {
+        let (committee, _keys) = Committee::new_simple_test_committee();
+        // Test committee keys come from a fixed seed, so a second committee of
+        // the same size would be identical; one more validator makes it differ.
+        let (other_committee, _other_keys) = Committee::new_simple_test_committee_of_size(5);
+        let summary = summary_with_next_committee(&other_committee);
+
+        let failure = verify_committee_transition(&summary, &committee)
+            .expect_err("a committee derived from different validators must not match");
+        assert_eq!(failure.checkpoint_seq, summary.sequence_number);
+    }
+}